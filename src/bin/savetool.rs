@@ -0,0 +1,137 @@
+use std::env;
+use std::path::Path;
+use std::process::exit;
+
+use bevy::prelude::*;
+use bevy::utils::get_short_name;
+
+use game::plugins::save_load::config::SaveConfig;
+use game::plugins::save_load::diff::{diff_scenes, EntityKey};
+use game::plugins::save_load::error::SaveError;
+use game::plugins::save_load::format::convert;
+use game::plugins::save_load::hash::scene_hash;
+use game::plugins::save_load::slots::SaveSlots;
+use game::plugins::save_load::systems::read_save;
+use game::plugins::Components;
+
+const USAGE: &str = "Usage: savetool [--save-dir <dir>] <command>
+
+Commands:
+    list                  List save slots with their metadata
+    show <save>           Print the entities and components of a save
+    validate <save>       Check that every component of a save is registered
+    hash <save>           Print the stable hash of the saved entities
+    diff <save> <save>    Print the entities, components and fields that differ between two saves
+    convert <from> <to>   Convert a save file, the format follows the file extension
+
+A <save> is either a path to a save file or the name of a save slot.";
+
+fn main() {
+    let args = command_args();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    // `CorePlugin` registers the std types like `String` that components are built from
+    let mut app = App::new();
+    app.add_plugin(CorePlugin::default())
+        .add_plugin(Components::default());
+    let type_registry = app.world.resource::<AppTypeRegistry>().clone();
+    let slots = SaveSlots::new(SaveConfig::from_env().dir);
+
+    let result = match args[..] {
+        ["list"] => list(&slots),
+        ["show", save] => read(&slots, save, &type_registry).map(|scene| show(&scene)),
+        ["validate", save] => read(&slots, save, &type_registry).map(|scene| validate(&scene, &type_registry)),
+        ["hash", save] => read(&slots, save, &type_registry).map(|scene| {
+            println!("{:016x}", scene_hash(&scene));
+            true
+        }),
+        ["diff", a, b] => read(&slots, a, &type_registry)
+            .and_then(|a| Ok((a, read(&slots, b, &type_registry)?)))
+            .map(|(a, b)| diff(&a, &b)),
+        ["convert", from, to] => convert(Path::new(from), Path::new(to), &type_registry).map(|_| true),
+        _ => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        }
+    };
+
+    match result {
+        Ok(true) => {}
+        Ok(false) => exit(1),
+        Err(error) => {
+            eprintln!("{}", error);
+            exit(1);
+        }
+    }
+}
+
+// `--save-dir` is read by `SaveConfig::from_env`, so it only has to be skipped here
+fn command_args() -> Vec<String> {
+    let mut args = vec![];
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        if arg == "--save-dir" {
+            iter.next();
+        } else if !arg.starts_with("--save-dir=") {
+            args.push(arg);
+        }
+    }
+    args
+}
+
+fn read(slots: &SaveSlots, save: &str, type_registry: &AppTypeRegistry) -> Result<DynamicScene, SaveError> {
+    let path = Path::new(save);
+    if path.is_file() {
+        read_save(path, type_registry)
+    } else {
+        slots.metadata(save)?;
+        slots.read_scene(save, type_registry)
+    }
+}
+
+fn list(slots: &SaveSlots) -> Result<bool, SaveError> {
+    for slot in slots.list()? {
+        println!(
+            "{:<24} modified {:>10}  played {:>6}s  {:>4} entities  v{}",
+            slot.name, slot.modified, slot.play_time.as_secs(), slot.entity_count, slot.game_version
+        );
+    }
+    Ok(true)
+}
+
+fn show(scene: &DynamicScene) -> bool {
+    println!("{} entities", scene.entities.len());
+    for entity in &scene.entities {
+        println!("{}", EntityKey::of(entity));
+        for component in &entity.components {
+            println!("    {}", get_short_name(component.type_name()));
+        }
+    }
+    true
+}
+
+fn validate(scene: &DynamicScene, type_registry: &AppTypeRegistry) -> bool {
+    let type_registry = type_registry.read();
+    let mut valid = true;
+    for entity in &scene.entities {
+        for component in &entity.components {
+            let registered = type_registry
+                .get_with_name(component.type_name())
+                .is_some_and(|registration| registration.data::<ReflectComponent>().is_some());
+            if !registered {
+                println!("{}: {} is not a registered component", entity.entity, component.type_name());
+                valid = false;
+            }
+        }
+    }
+    if valid {
+        println!("OK, {} entities", scene.entities.len());
+    }
+    valid
+}
+
+fn diff(a: &DynamicScene, b: &DynamicScene) -> bool {
+    let diff = diff_scenes(a, b);
+    print!("{}", diff);
+    diff.is_empty()
+}
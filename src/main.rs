@@ -1,9 +1,8 @@
 use bevy::*;
 use bevy_ecs::system::Commands;
 
-use plugins::components::*;
-
-mod plugins;
+use game::plugins;
+use game::plugins::components::*;
 
 fn main() {
     let window_descriptor = window::WindowDescriptor {
@@ -30,11 +29,11 @@ fn main() {
 }
 
 fn add_entities(mut commands: Commands) {
-    let vertebrae1 = Vertebrae { position: [0.0, 0.0, 0.0, 0.0], color: [0.0, 1.0, 0.0, 1.0] };
-    let vertebrae2 = Vertebrae { position: [0.1, 0.1, 0.0, 0.0], color: [1.0, 1.0, 0.0, 1.0] };
-    let vertebrae3 = Vertebrae { position: [0.5, 0.0, 0.0, 0.0], color: [1.0, 1.0, 1.0, 1.0] };
-    commands.spawn((Body { spine: vec![vertebrae1, vertebrae2, vertebrae3] }, Save::default()));
-    let vertebrae = Vertebrae { position: [-0.5, -0.5, 0.0, 0.0], color: [0.0, 0.0, 1.0, 1.0] };
+    let vertebrae1 = Vertebrae { position: [0.0, 0.0, 0.0, 1.0], color: [0.0, 1.0, 0.0, 1.0] };
+    let vertebrae2 = Vertebrae { position: [0.1, 0.1, 0.0, 1.0], color: [1.0, 1.0, 0.0, 1.0] };
+    let vertebrae3 = Vertebrae { position: [0.5, 0.0, 0.0, 1.0], color: [1.0, 1.0, 1.0, 1.0] };
+    commands.spawn((Body { spine: vec![vertebrae1, vertebrae2, vertebrae3] }, BodyRenderStyle::Polyline { width: 0.02 }, Save::default()));
+    let vertebrae = Vertebrae { position: [-0.5, -0.5, 0.0, 1.0], color: [0.0, 0.0, 1.0, 1.0] };
     commands.spawn((Body { spine: vec![vertebrae] }, Name("TestWithSpine".to_string()), Save::default()));
     commands.spawn((Name("SpinelessOne".to_string()), Save::default()));
 }
@@ -1,7 +1,7 @@
 mod vulkan;
-mod save_load;
+pub mod save_load;
 pub mod components;
 
-pub use vulkan::{VulkanPlugin,get_vulkano_config,resources::ViewportResource};
+pub use vulkan::{VulkanPlugin,get_vulkano_config,camera::Camera2d,resources::{ViewportResource, ViewportPolicy}};
 pub use save_load::SaveLoad;
 pub use components::Components;
\ No newline at end of file
@@ -15,7 +15,10 @@ impl Components {
 impl Plugin for Components {
     fn build(&self, app: &mut App) {
         app.register_type::<Body>()
+            .register_type::<BodyRenderStyle>()
             .register_type::<Name>()
+            .register_type::<Save>()
+            .register_type::<PersistentId>()
             .register_type::<Vertebrae>()
             .register_type::<Vec<Vertebrae>>()
             .register_type::<[f32; 2]>()
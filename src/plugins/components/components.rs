@@ -1,5 +1,6 @@
 use bevy::ecs::component::Component;
 use bevy::ecs::reflect::ReflectComponent;
+use bevy::utils::Uuid;
 use bevy_reflect::{FromReflect, Reflect, std_traits::ReflectDefault};
 
 pub use body_subparts::*;
@@ -22,10 +23,40 @@ impl Body {
     }
 }
 
+// Bodies without the component are drawn as points. The polyline width is in world units.
+#[derive(Component, Default, Reflect, FromReflect, Clone, Copy, Debug, PartialEq)]
+#[reflect(Component, Default)]
+pub enum BodyRenderStyle {
+    #[default]
+    Points,
+    LineStrip,
+    Polyline { width: f32 },
+}
+
 #[derive(Component, Default, Reflect, FromReflect)]
 #[reflect(Component, Default)]
 pub struct Name(pub String);
 
 #[derive(Component, Default, Reflect, FromReflect)]
 #[reflect(Component, Default)]
-pub struct Save;
\ No newline at end of file
+pub struct Save;
+
+// Random so that ids from different sessions or merged saves do not run into each other
+#[derive(Component, Reflect, FromReflect, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[reflect(Component, Default)]
+pub struct PersistentId(pub u64);
+
+impl PersistentId {
+    pub fn new() -> Self {
+        // The low half alone carries the fixed UUID variant bits, folding in the high half keeps them random
+        let bits = Uuid::new_v4().as_u128();
+        Self((bits >> 64) as u64 ^ bits as u64)
+    }
+}
+
+// Reflection builds components from their default, a fixed id would be shared by all of them
+impl Default for PersistentId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,8 +1,53 @@
-use bevy::app::{App, Plugin};
+use bevy::app::{App, CoreStage, Plugin};
+use bevy::asset::AddAsset;
+use bevy::ecs::schedule::IntoSystemDescriptor;
+use bevy::input::InputSystem;
+use bevy::window::{CursorMoved, WindowFocused};
 
+use autosave::{autosave_game, detect_autosave_triggers, Autosave};
+use background::{poll_save_tasks, SaveCompleted, SaveStatus};
+use config::SaveConfig;
+use journal::{journal_hotkeys, replay_journal, update_journal, JournalMode};
+use prefab::{spawn_pending_bodies, BodyPrefab, BodyPrefabLoader};
+use post_load::{detect_completed_loads, AddLoadValidator, LoadCompleted};
+use persistent::{assign_persistent_ids, index_persistent_ids, PersistentIndex};
+use error::SaveLoadFailed;
+use scene::SaveComponents;
+use slots::SaveSlots;
+use snapshot::{snapshot_history, SnapshotHistory};
 use systems::*;
 
+use crate::plugins::components::{Body, BodyRenderStyle, Name, PersistentId, Save};
+
 pub mod systems;
+pub mod scene;
+pub mod error;
+pub mod slots;
+pub mod manifest;
+pub mod storage;
+pub mod migration;
+pub mod format;
+pub mod autosave;
+pub mod background;
+pub mod merge;
+pub mod config;
+pub mod diff;
+pub mod snapshot;
+pub mod persistent;
+pub mod thumbnail;
+pub mod post_load;
+pub mod journal;
+pub mod hash;
+pub mod prefab;
+
+pub fn saved_components() -> SaveComponents {
+    SaveComponents::default()
+        .with::<Body>()
+        .with::<BodyRenderStyle>()
+        .with::<Name>()
+        .with::<Save>()
+        .with::<PersistentId>()
+}
 
 pub struct SaveLoad {}
 
@@ -14,8 +59,40 @@ impl SaveLoad {
 
 impl Plugin for SaveLoad {
     fn build(&self, app: &mut App) {
-        app.add_system(quick_save_game)
-            .add_system(quick_load_game);
+        // A config inserted before the plugin is added takes precedence over the environment
+        if !app.world.contains_resource::<SaveConfig>() {
+            app.insert_resource(SaveConfig::from_env());
+        }
+        let save_dir = app.world.resource::<SaveConfig>().dir.clone();
+
+        app.insert_resource(saved_components())
+            .insert_resource(SaveSlots::new(save_dir))
+            .init_resource::<Autosave>()
+            .init_resource::<SaveStatus>()
+            .init_resource::<SnapshotHistory>()
+            .init_resource::<PersistentIndex>()
+            .init_resource::<JournalMode>()
+            .add_asset::<BodyPrefab>()
+            .init_asset_loader::<BodyPrefabLoader>()
+            .add_event::<SaveLoadFailed>()
+            .add_event::<SaveCompleted>()
+            .add_event::<LoadCompleted>()
+            .add_event::<WindowFocused>()
+            .add_event::<CursorMoved>()
+            .add_system(quick_save_game)
+            .add_system(quick_load_game)
+            .add_system(poll_save_tasks)
+            .add_system(snapshot_history)
+            .add_system(detect_completed_loads)
+            .add_load_validator::<Body>()
+            .add_system(journal_hotkeys)
+            .add_system(spawn_pending_bodies)
+            .add_system_to_stage(CoreStage::PreUpdate, replay_journal.after(InputSystem))
+            .add_system_to_stage(CoreStage::PostUpdate, assign_persistent_ids)
+            .add_system_to_stage(CoreStage::Last, index_persistent_ids)
+            .add_system_to_stage(CoreStage::Last, detect_autosave_triggers)
+            .add_system_to_stage(CoreStage::Last, autosave_game.after(detect_autosave_triggers))
+            .add_system_to_stage(CoreStage::Last, update_journal);
     }
 
     fn name(&self) -> &str {
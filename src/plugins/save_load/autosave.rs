@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::window::WindowFocused;
+
+use super::background::{flush_save_tasks, save_in_background, SaveStatus};
+use super::error::SaveOperation;
+use super::slots::SaveSlots;
+use super::systems::report_failure;
+
+pub const AUTOSAVE_SLOT: &str = "autosave";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutosaveReason {
+    Interval,
+    Exit,
+    FocusLost,
+}
+
+#[derive(Resource)]
+pub struct Autosave {
+    pub enabled: bool,
+    pub interval: Option<Duration>,
+    pub on_exit: bool,
+    pub on_focus_lost: bool,
+    pub rotation: u32,
+    pending: Option<AutosaveReason>,
+    last_save: Duration,
+}
+
+impl Default for Autosave {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: Some(Duration::from_secs(300)),
+            on_exit: true,
+            on_focus_lost: true,
+            rotation: 3,
+            pending: None,
+            last_save: Duration::ZERO,
+        }
+    }
+}
+
+pub fn detect_autosave_triggers(
+    time: Res<Time>,
+    mut autosave: ResMut<Autosave>,
+    mut exit_events: EventReader<AppExit>,
+    mut focus_events: EventReader<WindowFocused>,
+) {
+    let exiting = exit_events.iter().count() > 0;
+    let focus_lost = focus_events.iter().any(|event| !event.focused);
+    if !autosave.enabled || autosave.pending.is_some() {
+        return;
+    }
+
+    let interval_elapsed = autosave.interval
+        .is_some_and(|interval| time.elapsed().saturating_sub(autosave.last_save) >= interval);
+
+    autosave.pending = if exiting && autosave.on_exit {
+        Some(AutosaveReason::Exit)
+    } else if focus_lost && autosave.on_focus_lost {
+        Some(AutosaveReason::FocusLost)
+    } else if interval_elapsed {
+        Some(AutosaveReason::Interval)
+    } else {
+        None
+    };
+}
+
+pub fn autosave_game(world: &mut World) {
+    let (reason, rotation) = {
+        let autosave = world.resource::<Autosave>();
+        match autosave.pending {
+            Some(reason) => (reason, autosave.rotation),
+            None => return,
+        }
+    };
+
+    // The process ends right after this frame, so the exit autosave cannot be left to a task
+    if reason == AutosaveReason::Exit {
+        flush_save_tasks(world);
+    } else if world.resource::<SaveStatus>().is_saving(AUTOSAVE_SLOT) {
+        return;
+    }
+
+    let now = world.resource::<Time>().elapsed();
+    {
+        let mut autosave = world.resource_mut::<Autosave>();
+        autosave.pending = None;
+        autosave.last_save = now;
+    }
+
+    let result = if reason == AutosaveReason::Exit {
+        world.resource::<SaveSlots>().save_rotating(world, AUTOSAVE_SLOT, rotation).map(|_| ())
+    } else {
+        save_in_background(world, AUTOSAVE_SLOT, rotation)
+    };
+
+    match result {
+        Ok(()) => info!("Autosave started ({:?})", reason),
+        Err(error) => report_failure(world, SaveOperation::Save, error),
+    }
+}
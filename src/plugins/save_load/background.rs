@@ -0,0 +1,125 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy::tasks::{IoTaskPool, Task};
+use futures_lite::future;
+
+use super::error::{SaveError, SaveLoadFailed, SaveOperation};
+use super::slots::{SaveSlots, SlotMetadata};
+use super::systems::report_failure;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveStage {
+    Snapshotted,
+    Encoding,
+    Writing,
+}
+
+impl SaveStage {
+    fn from_u8(stage: u8) -> Self {
+        match stage {
+            0 => SaveStage::Snapshotted,
+            1 => SaveStage::Encoding,
+            _ => SaveStage::Writing,
+        }
+    }
+}
+
+pub struct SaveCompleted {
+    pub metadata: SlotMetadata,
+}
+
+struct SaveTask {
+    slot: String,
+    stage: Arc<AtomicU8>,
+    task: Task<Result<SlotMetadata, SaveError>>,
+}
+
+#[derive(Resource, Default)]
+pub struct SaveStatus {
+    tasks: Vec<SaveTask>,
+}
+
+impl SaveStatus {
+    pub fn is_saving(&self, slot: &str) -> bool {
+        self.tasks.iter().any(|save| save.slot == slot)
+    }
+
+    pub fn in_progress(&self) -> impl Iterator<Item=(&str, SaveStage)> {
+        self.tasks.iter().map(|save| (save.slot.as_str(), SaveStage::from_u8(save.stage.load(Ordering::Relaxed))))
+    }
+
+    fn finish(&mut self, block: bool) -> Vec<Result<SlotMetadata, SaveError>> {
+        let mut finished = vec![];
+        self.tasks.retain_mut(|save| {
+            let result = if block {
+                Some(future::block_on(&mut save.task))
+            } else {
+                future::block_on(future::poll_once(&mut save.task))
+            };
+            match result {
+                Some(result) => {
+                    finished.push(result);
+                    false
+                }
+                None => true,
+            }
+        });
+        finished
+    }
+}
+
+// Only the snapshot is taken in the frame, encoding and file IO happen on the IO task pool.
+pub fn save_in_background(world: &mut World, name: &str, rotation: u32) -> Result<(), SaveError> {
+    if world.resource::<SaveStatus>().is_saving(name) {
+        return Err(SaveError::SaveInProgress(name.to_string()));
+    }
+
+    let snapshot = world.resource::<SaveSlots>().snapshot(world, name, rotation)?;
+    let stage = Arc::new(AtomicU8::new(SaveStage::Snapshotted as u8));
+    let task_stage = stage.clone();
+    let task = IoTaskPool::get().spawn(async move {
+        snapshot.write(|stage| task_stage.store(stage as u8, Ordering::Relaxed))
+    });
+
+    world.resource_mut::<SaveStatus>().tasks.push(SaveTask {
+        slot: name.to_string(),
+        stage,
+        task,
+    });
+
+    Ok(())
+}
+
+pub fn poll_save_tasks(
+    mut status: ResMut<SaveStatus>,
+    mut completed: EventWriter<SaveCompleted>,
+    mut failed: EventWriter<SaveLoadFailed>,
+) {
+    for result in status.finish(false) {
+        match result {
+            Ok(metadata) => {
+                info!("Saved {}", metadata.name);
+                completed.send(SaveCompleted { metadata });
+            }
+            Err(error) => {
+                error!("{:?} failed: {}", SaveOperation::Save, error);
+                failed.send(SaveLoadFailed { operation: SaveOperation::Save, error });
+            }
+        }
+    }
+}
+
+pub fn flush_save_tasks(world: &mut World) {
+    let results = world.resource_mut::<SaveStatus>().finish(true);
+    for result in results {
+        match result {
+            Ok(metadata) => {
+                info!("Saved {}", metadata.name);
+                world.send_event(SaveCompleted { metadata });
+            }
+            Err(error) => report_failure(world, SaveOperation::Save, error),
+        }
+    }
+}
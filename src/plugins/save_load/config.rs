@@ -0,0 +1,61 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+
+pub const SAVE_DIR_ENV: &str = "GAME_SAVE_DIR";
+pub const SAVE_DIR_FLAG: &str = "--save-dir";
+
+#[derive(Resource, Clone, Debug)]
+pub struct SaveConfig {
+    pub dir: PathBuf,
+}
+
+impl Default for SaveConfig {
+    fn default() -> Self {
+        Self { dir: default_save_dir() }
+    }
+}
+
+impl SaveConfig {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self { dir: absolute(dir.as_ref()) }
+    }
+
+    // The command line flag wins over the environment variable, which wins over the XDG default
+    pub fn from_env() -> Self {
+        save_dir_arg(env::args().skip(1))
+            .or_else(|| env::var_os(SAVE_DIR_ENV).filter(|dir| !dir.is_empty()).map(PathBuf::from))
+            .map_or_else(Self::default, Self::new)
+    }
+}
+
+fn save_dir_arg(mut args: impl Iterator<Item=String>) -> Option<PathBuf> {
+    while let Some(arg) = args.next() {
+        if arg == SAVE_DIR_FLAG {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(dir) = arg.strip_prefix(SAVE_DIR_FLAG).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(PathBuf::from(dir));
+        }
+    }
+    None
+}
+
+// https://specifications.freedesktop.org/basedir-spec/ says relative values have to be ignored
+fn default_save_dir() -> PathBuf {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))
+        .unwrap_or_else(|| absolute(Path::new(".")));
+    data_home.join(env!("CARGO_PKG_NAME")).join("saves")
+}
+
+fn absolute(dir: &Path) -> PathBuf {
+    if dir.is_absolute() {
+        dir.to_path_buf()
+    } else {
+        env::current_dir().map_or_else(|_| dir.to_path_buf(), |current_dir| current_dir.join(dir))
+    }
+}
@@ -0,0 +1,221 @@
+use std::fmt::{Display, Formatter};
+
+use bevy::prelude::*;
+use bevy::reflect::ReflectRef;
+use bevy::scene::DynamicEntity;
+use bevy::utils::get_short_name;
+
+use crate::plugins::components::{Name, PersistentId};
+
+use super::scene::build_save_scene;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EntityKey {
+    Persistent(PersistentId),
+    Name(String),
+    Id(u32),
+}
+
+impl EntityKey {
+    // Persistent ids and names survive a save and load round trip, entity ids only line up
+    // between saves of the same session
+    pub fn of(entity: &DynamicEntity) -> Self {
+        find_component(entity, std::any::type_name::<PersistentId>())
+            .and_then(PersistentId::from_reflect)
+            .map(EntityKey::Persistent)
+            .or_else(|| find_component(entity, std::any::type_name::<Name>())
+                .and_then(Name::from_reflect)
+                .map(|name| EntityKey::Name(name.0)))
+            .unwrap_or(EntityKey::Id(entity.entity))
+    }
+}
+
+impl Display for EntityKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EntityKey::Persistent(id) => write!(f, "@{:016x}", id.0),
+            EntityKey::Name(name) => write!(f, "{}", name),
+            EntityKey::Id(id) => write!(f, "#{}", id),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldChange {
+    pub path: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ComponentChange {
+    Added(String),
+    Removed(String),
+    Changed(String, Vec<FieldChange>),
+}
+
+#[derive(Debug, Clone)]
+pub struct EntityDiff {
+    pub key: EntityKey,
+    pub changes: Vec<ComponentChange>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SceneDiff {
+    pub added: Vec<EntityKey>,
+    pub removed: Vec<EntityKey>,
+    pub changed: Vec<EntityDiff>,
+}
+
+impl SceneDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl Display for SceneDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for key in &self.removed {
+            writeln!(f, "- {}", key)?;
+        }
+        for key in &self.added {
+            writeln!(f, "+ {}", key)?;
+        }
+        for entity in &self.changed {
+            for change in &entity.changes {
+                match change {
+                    ComponentChange::Added(type_name) => writeln!(f, "+ {} {}", entity.key, get_short_name(type_name))?,
+                    ComponentChange::Removed(type_name) => writeln!(f, "- {} {}", entity.key, get_short_name(type_name))?,
+                    ComponentChange::Changed(type_name, fields) => {
+                        writeln!(f, "~ {} {}", entity.key, get_short_name(type_name))?;
+                        for field in fields {
+                            let before = field.before.as_deref().unwrap_or("<none>");
+                            let after = field.after.as_deref().unwrap_or("<none>");
+                            writeln!(f, "    {}: {} -> {}", field.path, before, after)?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn diff_scenes(before: &DynamicScene, after: &DynamicScene) -> SceneDiff {
+    let before_keys: Vec<EntityKey> = before.entities.iter().map(EntityKey::of).collect();
+    let after_keys: Vec<EntityKey> = after.entities.iter().map(EntityKey::of).collect();
+
+    let mut diff = SceneDiff::default();
+    for (entity, key) in before.entities.iter().zip(&before_keys) {
+        match after_keys.iter().position(|after_key| after_key == key) {
+            None => diff.removed.push(key.clone()),
+            Some(index) => {
+                let changes = diff_components(entity, &after.entities[index]);
+                if !changes.is_empty() {
+                    diff.changed.push(EntityDiff { key: key.clone(), changes });
+                }
+            }
+        }
+    }
+    diff.added = after_keys.into_iter().filter(|key| !before_keys.contains(key)).collect();
+
+    diff
+}
+
+// Compares a save against what the world would write right now
+pub fn diff_world(saved: &DynamicScene, world: &World) -> SceneDiff {
+    diff_scenes(saved, &build_save_scene(world))
+}
+
+fn find_component<'a>(entity: &'a DynamicEntity, type_name: &str) -> Option<&'a dyn Reflect> {
+    entity.components.iter()
+        .find(|component| component.type_name() == type_name)
+        .map(|component| &**component)
+}
+
+fn diff_components(before: &DynamicEntity, after: &DynamicEntity) -> Vec<ComponentChange> {
+    let mut changes = vec![];
+    for component in &before.components {
+        let type_name = component.type_name().to_string();
+        match find_component(after, &type_name) {
+            None => changes.push(ComponentChange::Removed(type_name)),
+            Some(other) => {
+                let mut fields = vec![];
+                diff_fields(String::new(), &**component, other, &mut fields);
+                if !fields.is_empty() {
+                    changes.push(ComponentChange::Changed(type_name, fields));
+                }
+            }
+        }
+    }
+    for component in &after.components {
+        if find_component(before, component.type_name()).is_none() {
+            changes.push(ComponentChange::Added(component.type_name().to_string()));
+        }
+    }
+
+    changes
+}
+
+// Paths use the same syntax as `GetPath`, so `spine[0].position[1]` can be looked up again
+fn diff_fields(path: String, before: &dyn Reflect, after: &dyn Reflect, changes: &mut Vec<FieldChange>) {
+    let field = |name: &str| if path.is_empty() { name.to_string() } else { format!("{}.{}", path, name) };
+    let index = |index: usize| format!("{}[{}]", path, index);
+
+    match (before.reflect_ref(), after.reflect_ref()) {
+        (ReflectRef::Struct(before), ReflectRef::Struct(after)) => {
+            for (i, value) in before.iter_fields().enumerate() {
+                let name = before.name_at(i).unwrap_or_default();
+                diff_optional(field(name), Some(value), after.field(name), changes);
+            }
+            for (i, value) in after.iter_fields().enumerate() {
+                let name = after.name_at(i).unwrap_or_default();
+                if before.field(name).is_none() {
+                    diff_optional(field(name), None, Some(value), changes);
+                }
+            }
+        }
+        (ReflectRef::TupleStruct(before), ReflectRef::TupleStruct(after)) => {
+            for i in 0..before.field_len().max(after.field_len()) {
+                diff_optional(field(&i.to_string()), before.field(i), after.field(i), changes);
+            }
+        }
+        (ReflectRef::Tuple(before), ReflectRef::Tuple(after)) => {
+            for i in 0..before.field_len().max(after.field_len()) {
+                diff_optional(field(&i.to_string()), before.field(i), after.field(i), changes);
+            }
+        }
+        (ReflectRef::List(before), ReflectRef::List(after)) => {
+            for i in 0..before.len().max(after.len()) {
+                diff_optional(index(i), before.get(i), after.get(i), changes);
+            }
+        }
+        (ReflectRef::Array(before), ReflectRef::Array(after)) => {
+            for i in 0..before.len().max(after.len()) {
+                diff_optional(index(i), before.get(i), after.get(i), changes);
+            }
+        }
+        (ReflectRef::Enum(before_enum), ReflectRef::Enum(after_enum)) if before_enum.variant_name() == after_enum.variant_name() => {
+            for i in 0..before_enum.field_len().max(after_enum.field_len()) {
+                let name = before_enum.name_at(i).or_else(|| after_enum.name_at(i)).map_or_else(|| i.to_string(), str::to_string);
+                diff_optional(field(&name), before_enum.field_at(i), after_enum.field_at(i), changes);
+            }
+        }
+        _ => {
+            if before.reflect_partial_eq(after) != Some(true) {
+                changes.push(FieldChange { path, before: Some(format!("{:?}", before)), after: Some(format!("{:?}", after)) });
+            }
+        }
+    }
+}
+
+fn diff_optional(path: String, before: Option<&dyn Reflect>, after: Option<&dyn Reflect>, changes: &mut Vec<FieldChange>) {
+    match (before, after) {
+        (Some(before), Some(after)) => diff_fields(path, before, after, changes),
+        (before, after) => changes.push(FieldChange {
+            path,
+            before: before.map(|before| format!("{:?}", before)),
+            after: after.map(|after| format!("{:?}", after)),
+        }),
+    }
+}
@@ -0,0 +1,78 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::PathBuf;
+
+use bevy::ecs::entity::Entity;
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Serialize(ron::Error),
+    Deserialize(ron::Error),
+    Binary(bincode::Error),
+    Thumbnail(png::EncodingError),
+    UnknownFormat(PathBuf),
+    InvalidSlotName(String),
+    SlotExists(String),
+    SlotNotFound(String),
+    ReservedSlot(String),
+    ChecksumMismatch(PathBuf),
+    UnsupportedVersion(u32),
+    SaveInProgress(String),
+    UnregisteredType(String),
+    NotABody(Entity),
+    NoSaveFound,
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::Io(error) => write!(f, "Save file IO failed: {}", error),
+            SaveError::Serialize(error) => write!(f, "Failed to serialize save: {}", error),
+            SaveError::Deserialize(error) => write!(f, "Failed to deserialize save: {}", error),
+            SaveError::Binary(error) => write!(f, "Failed to encode binary save: {}", error),
+            SaveError::Thumbnail(error) => write!(f, "Failed to encode save thumbnail: {}", error),
+            SaveError::UnknownFormat(path) => write!(f, "Unknown save format: {}", path.display()),
+            SaveError::InvalidSlotName(name) => write!(f, "Invalid save slot name: {:?}", name),
+            SaveError::SlotExists(name) => write!(f, "Save slot {:?} already exists", name),
+            SaveError::SlotNotFound(name) => write!(f, "Save slot {:?} not found", name),
+            SaveError::ReservedSlot(name) => write!(f, "Save slot {:?} is reserved", name),
+            SaveError::ChecksumMismatch(path) => write!(f, "Checksum mismatch in {}", path.display()),
+            SaveError::UnsupportedVersion(version) => write!(f, "Save format version {} is not supported", version),
+            SaveError::SaveInProgress(name) => write!(f, "Save slot {:?} is still being written", name),
+            SaveError::UnregisteredType(type_name) => write!(f, "No reflect component registration found for `{}`", type_name),
+            SaveError::NotABody(entity) => write!(f, "{:?} has no body to export", entity),
+            SaveError::NoSaveFound => write!(f, "No save file found"),
+        }
+    }
+}
+
+impl Error for SaveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SaveError::Io(error) => Some(error),
+            SaveError::Serialize(error) | SaveError::Deserialize(error) => Some(error),
+            SaveError::Binary(error) => Some(error),
+            SaveError::Thumbnail(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SaveError {
+    fn from(error: io::Error) -> Self {
+        SaveError::Io(error)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveOperation {
+    Save,
+    Load,
+}
+
+pub struct SaveLoadFailed {
+    pub operation: SaveOperation,
+    pub error: SaveError,
+}
@@ -0,0 +1,82 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy::scene::serde::{SceneDeserializer, SceneSerializer};
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+
+use super::error::SaveError;
+use super::migration::{deserialize_versioned, serialize_versioned, SAVE_FORMAT_VERSION};
+use super::storage::write_atomic;
+
+const COMPRESSION_LEVEL: i32 = 3;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SaveFormat {
+    Ron,
+    Binary,
+    CompressedBinary,
+}
+
+impl SaveFormat {
+    pub const ALL: [SaveFormat; 3] = [SaveFormat::Ron, SaveFormat::Binary, SaveFormat::CompressedBinary];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            SaveFormat::Ron => "scn.ron",
+            SaveFormat::Binary => "scn.bin",
+            SaveFormat::CompressedBinary => "scn.bin.zst",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Result<Self, SaveError> {
+        let file_name = path.file_name().and_then(|file_name| file_name.to_str()).unwrap_or_default();
+        Self::ALL.into_iter()
+            .find(|format| file_name.ends_with(&(".".to_string() + format.extension())))
+            .ok_or_else(|| SaveError::UnknownFormat(path.to_path_buf()))
+    }
+}
+
+pub fn encode(scene: &DynamicScene, type_registry: &AppTypeRegistry, format: SaveFormat) -> Result<Vec<u8>, SaveError> {
+    match format {
+        SaveFormat::Ron => Ok(serialize_versioned(scene, type_registry)?.into_bytes()),
+        SaveFormat::Binary => encode_binary(scene, type_registry),
+        SaveFormat::CompressedBinary => Ok(zstd::encode_all(&encode_binary(scene, type_registry)?[..], COMPRESSION_LEVEL)?),
+    }
+}
+
+pub fn decode(bytes: &[u8], type_registry: &AppTypeRegistry, format: SaveFormat) -> Result<DynamicScene, SaveError> {
+    match format {
+        SaveFormat::Ron => deserialize_versioned(bytes, type_registry),
+        SaveFormat::Binary => decode_binary(bytes, type_registry),
+        SaveFormat::CompressedBinary => decode_binary(&zstd::decode_all(bytes)?, type_registry),
+    }
+}
+
+pub fn convert(from: &Path, to: &Path, type_registry: &AppTypeRegistry) -> Result<(), SaveError> {
+    let scene = decode(&std::fs::read(from)?, type_registry, SaveFormat::from_path(from)?)?;
+    write_atomic(to, &encode(&scene, type_registry, SaveFormat::from_path(to)?)?)
+}
+
+fn encode_binary(scene: &DynamicScene, type_registry: &AppTypeRegistry) -> Result<Vec<u8>, SaveError> {
+    let mut bytes = bincode::serialize(&SAVE_FORMAT_VERSION).map_err(SaveError::Binary)?;
+    bytes.extend(bincode::serialize(&SceneSerializer::new(scene, &type_registry.0)).map_err(SaveError::Binary)?);
+    Ok(bytes)
+}
+
+// Binary saves are not self-describing, so the RON migrations cannot walk them and only the
+// current version can be read back. Convert to RON before bumping the format version.
+fn decode_binary(bytes: &[u8], type_registry: &AppTypeRegistry) -> Result<DynamicScene, SaveError> {
+    let version: u32 = bincode::deserialize(bytes).map_err(SaveError::Binary)?;
+    if version != SAVE_FORMAT_VERSION {
+        return Err(SaveError::UnsupportedVersion(version));
+    }
+
+    let scene_deserializer = SceneDeserializer {
+        type_registry: &type_registry.read(),
+    };
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .deserialize_seed(scene_deserializer, &bytes[4..])
+        .map_err(SaveError::Binary)
+}
@@ -0,0 +1,182 @@
+use std::any::type_name;
+
+use bevy::prelude::*;
+use bevy::reflect::ReflectRef;
+use bevy::scene::DynamicEntity;
+
+use crate::plugins::components::PersistentId;
+
+use super::scene::build_save_scene;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+// FNV-1a, unlike the std and ahash hashers its output is fixed across Rust and crate versions
+struct StableHasher(u64);
+
+impl StableHasher {
+    fn new() -> Self {
+        Self(FNV_OFFSET)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_str(&mut self, value: &str) {
+        self.write_u64(value.len() as u64);
+        self.write(value.as_bytes());
+    }
+}
+
+// Hashes what `build_save_scene` would write, so only `Save` entities and allow-listed components count
+pub fn world_hash(world: &World) -> u64 {
+    scene_hash(&build_save_scene(world))
+}
+
+// Entity ids and the order of entities and components do not change the hash. `PersistentId`
+// is left out because it is random for every entity spawned after the save was loaded.
+pub fn scene_hash(scene: &DynamicScene) -> u64 {
+    let mut entities: Vec<u64> = scene.entities.iter().map(entity_hash).collect();
+    entities.sort_unstable();
+
+    let mut hasher = StableHasher::new();
+    hasher.write_u64(entities.len() as u64);
+    for entity in entities {
+        hasher.write_u64(entity);
+    }
+    hasher.0
+}
+
+fn entity_hash(entity: &DynamicEntity) -> u64 {
+    let mut components: Vec<(&str, u64)> = entity.components.iter()
+        .filter(|component| component.type_name() != type_name::<PersistentId>())
+        .map(|component| {
+            let mut hasher = StableHasher::new();
+            hash_value(&mut hasher, &**component);
+            (component.type_name(), hasher.0)
+        })
+        .collect();
+    components.sort_unstable();
+
+    let mut hasher = StableHasher::new();
+    for (type_name, hash) in components {
+        hasher.write_str(type_name);
+        hasher.write_u64(hash);
+    }
+    hasher.0
+}
+
+fn hash_value(hasher: &mut StableHasher, value: &dyn Reflect) {
+    match value.reflect_ref() {
+        // Sorted by name, a struct migrated from an older RON save has its fields in alphabetical order
+        ReflectRef::Struct(value) => {
+            hasher.write(b"struct");
+            let mut fields: Vec<(&str, &dyn Reflect)> = value.iter_fields()
+                .enumerate()
+                .map(|(i, field)| (value.name_at(i).unwrap_or_default(), field))
+                .collect();
+            fields.sort_unstable_by_key(|(name, _)| *name);
+            for (name, field) in fields {
+                hasher.write_str(name);
+                hash_value(hasher, field);
+            }
+        }
+        ReflectRef::TupleStruct(value) => {
+            hasher.write(b"tuple_struct");
+            for field in value.iter_fields() {
+                hash_value(hasher, field);
+            }
+        }
+        ReflectRef::Tuple(value) => {
+            hasher.write(b"tuple");
+            for field in value.iter_fields() {
+                hash_value(hasher, field);
+            }
+        }
+        ReflectRef::List(value) => {
+            hasher.write(b"list");
+            hasher.write_u64(value.len() as u64);
+            for item in value.iter() {
+                hash_value(hasher, item);
+            }
+        }
+        ReflectRef::Array(value) => {
+            hasher.write(b"array");
+            hasher.write_u64(value.len() as u64);
+            for item in value.iter() {
+                hash_value(hasher, item);
+            }
+        }
+        // Map iteration order is not defined, so entries are hashed on their own and sorted
+        ReflectRef::Map(value) => {
+            hasher.write(b"map");
+            let mut entries: Vec<u64> = value.iter()
+                .map(|(key, value)| {
+                    let mut entry = StableHasher::new();
+                    hash_value(&mut entry, key);
+                    hash_value(&mut entry, value);
+                    entry.0
+                })
+                .collect();
+            entries.sort_unstable();
+            hasher.write_u64(entries.len() as u64);
+            for entry in entries {
+                hasher.write_u64(entry);
+            }
+        }
+        ReflectRef::Enum(value) => {
+            hasher.write(b"enum");
+            hasher.write_str(value.variant_name());
+            for field in value.iter_fields() {
+                hasher.write_str(field.name().unwrap_or_default());
+                hash_value(hasher, field.value());
+            }
+        }
+        ReflectRef::Value(value) => hash_primitive(hasher, value),
+    }
+}
+
+// Floats are hashed by their bits after folding every NaN into one and -0.0 into 0.0, so values
+// that compare equal also hash equal. Entity references are skipped as their ids change on load.
+fn hash_primitive(hasher: &mut StableHasher, value: &dyn Reflect) {
+    let any = value.as_any();
+    if let Some(value) = any.downcast_ref::<f32>() {
+        let canonical = if value.is_nan() { f32::NAN } else if *value == 0.0 { 0.0 } else { *value };
+        hasher.write(&canonical.to_bits().to_le_bytes());
+    } else if let Some(value) = any.downcast_ref::<f64>() {
+        let canonical = if value.is_nan() { f64::NAN } else if *value == 0.0 { 0.0 } else { *value };
+        hasher.write(&canonical.to_bits().to_le_bytes());
+    } else if let Some(value) = any.downcast_ref::<String>() {
+        hasher.write_str(value);
+    } else if let Some(value) = any.downcast_ref::<bool>() {
+        hasher.write(&[*value as u8]);
+    } else if let Some(value) = any.downcast_ref::<char>() {
+        hasher.write_u64(*value as u64);
+    } else if let Some(value) = integer(any) {
+        hasher.write(&value.to_le_bytes());
+    } else if any.is::<Entity>() {
+        hasher.write(b"entity");
+    } else {
+        hasher.write_str(value.type_name());
+        hasher.write_str(&format!("{:?}", value));
+    }
+}
+
+fn integer(any: &dyn std::any::Any) -> Option<i128> {
+    macro_rules! downcast {
+        ($($ty:ty),*) => {
+            $(if let Some(value) = any.downcast_ref::<$ty>() {
+                return Some(*value as i128);
+            })*
+        };
+    }
+    downcast!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, i128);
+    any.downcast_ref::<u128>().map(|value| *value as i128)
+}
@@ -0,0 +1,281 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use bevy::ecs::event::{Events, ManualEventReader};
+use bevy::input::mouse::{MouseMotion, MouseWheel};
+use bevy::prelude::*;
+use bevy::window::{CursorMoved, WindowId};
+use serde::{Deserialize, Serialize};
+
+use crate::plugins::components::Save;
+
+use super::config::SaveConfig;
+use super::error::{SaveError, SaveOperation};
+use super::hash::world_hash;
+use super::merge::merge_scene;
+use super::storage::write_atomic;
+use super::systems::{read_save, report_failure, save_to};
+
+pub const JOURNAL_DIR: &str = "journal";
+const JOURNAL_FILE: &str = "input.journal";
+const JOURNAL_SAVE: &str = "start.scn.ron";
+const CHECKPOINT_INTERVAL: u32 = 60;
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct JournalFrame {
+    pub delta: Duration,
+    pub keys: Vec<KeyCode>,
+    pub mouse_buttons: Vec<MouseButton>,
+    pub mouse_motion: [f32; 2],
+    pub mouse_wheel: Vec<MouseWheel>,
+    // Where the cursor ended up on the primary window, if it moved
+    pub cursor_position: Option<[f32; 2]>,
+    pub checkpoint: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct InputJournal {
+    pub checkpoint_interval: u32,
+    pub frames: Vec<JournalFrame>,
+}
+
+impl InputJournal {
+    pub fn read(dir: &Path) -> Result<Self, SaveError> {
+        bincode::deserialize(&fs::read(dir.join(JOURNAL_FILE))?).map_err(SaveError::Binary)
+    }
+
+    pub fn write(&self, dir: &Path) -> Result<(), SaveError> {
+        write_atomic(&dir.join(JOURNAL_FILE), &bincode::serialize(self).map_err(SaveError::Binary)?)
+    }
+}
+
+// Mouse input arrives as events, each is read once per recorded frame
+#[derive(Default)]
+pub struct MouseReaders {
+    motion: ManualEventReader<MouseMotion>,
+    wheel: ManualEventReader<MouseWheel>,
+    cursor: ManualEventReader<CursorMoved>,
+}
+
+impl MouseReaders {
+    fn skip(&mut self, world: &World) {
+        self.motion.iter(world.resource::<Events<MouseMotion>>()).for_each(drop);
+        self.wheel.iter(world.resource::<Events<MouseWheel>>()).for_each(drop);
+        self.cursor.iter(world.resource::<Events<CursorMoved>>()).for_each(drop);
+    }
+}
+
+pub struct Replay {
+    journal: InputJournal,
+    frame: usize,
+    keys: Input<KeyCode>,
+    mouse_buttons: Input<MouseButton>,
+    time: Time,
+    mismatches: Vec<usize>,
+}
+
+// Starting and loading are deferred to the end of the frame, so the save a recording starts
+// from and the world a replay starts with are both taken after all game systems ran.
+#[derive(Resource, Default)]
+pub enum JournalMode {
+    #[default]
+    Idle,
+    StartRecording(PathBuf),
+    Recording(PathBuf, InputJournal, MouseReaders),
+    StartReplay(PathBuf),
+    Replaying(Box<Replay>),
+}
+
+impl JournalMode {
+    pub fn is_idle(&self) -> bool {
+        matches!(self, JournalMode::Idle)
+    }
+}
+
+pub fn start_recording(world: &mut World, dir: &Path) {
+    *world.resource_mut::<JournalMode>() = JournalMode::StartRecording(dir.to_path_buf());
+}
+
+pub fn stop_recording(world: &mut World) -> Result<(), SaveError> {
+    match std::mem::take(&mut *world.resource_mut::<JournalMode>()) {
+        JournalMode::Recording(dir, journal, _) => {
+            journal.write(&dir)?;
+            info!("Recorded {} frames to {}", journal.frames.len(), dir.display());
+            Ok(())
+        }
+        mode => {
+            *world.resource_mut::<JournalMode>() = mode;
+            Ok(())
+        }
+    }
+}
+
+pub fn start_replay(world: &mut World, dir: &Path) {
+    *world.resource_mut::<JournalMode>() = JournalMode::StartReplay(dir.to_path_buf());
+}
+
+pub fn journal_hotkeys(world: &mut World) {
+    let (toggle_recording, replay) = {
+        let input = world.resource::<Input<KeyCode>>();
+        (input.just_released(KeyCode::F7), input.just_released(KeyCode::F8))
+    };
+    let dir = world.resource::<SaveConfig>().dir.join(JOURNAL_DIR);
+
+    let recording = matches!(*world.resource::<JournalMode>(), JournalMode::Recording(..));
+    if toggle_recording && recording {
+        if let Err(error) = stop_recording(world) {
+            report_failure(world, SaveOperation::Save, error);
+        }
+    } else if toggle_recording && world.resource::<JournalMode>().is_idle() {
+        start_recording(world, &dir);
+    } else if replay && world.resource::<JournalMode>().is_idle() {
+        start_replay(world, &dir);
+    }
+}
+
+// Runs right after the input systems and overwrites what the devices reported with the journal
+pub fn replay_journal(world: &mut World) {
+    let mode = std::mem::take(&mut *world.resource_mut::<JournalMode>());
+    let mut replay = match mode {
+        JournalMode::Replaying(replay) if replay.frame < replay.journal.frames.len() => replay,
+        mode => {
+            *world.resource_mut::<JournalMode>() = mode;
+            return;
+        }
+    };
+
+    let frame = &replay.journal.frames[replay.frame];
+    replay.keys.clear();
+    replay.mouse_buttons.clear();
+    apply_pressed(&mut replay.keys, &frame.keys);
+    apply_pressed(&mut replay.mouse_buttons, &frame.mouse_buttons);
+    let last_update = replay.time.last_update().unwrap_or_else(|| replay.time.startup());
+    replay.time.update_with_instant(last_update + frame.delta);
+
+    *world.resource_mut::<Input<KeyCode>>() = replay.keys.clone();
+    *world.resource_mut::<Input<MouseButton>>() = replay.mouse_buttons.clone();
+    *world.resource_mut::<Time>() = replay.time.clone();
+    let mut mouse_motion = world.resource_mut::<Events<MouseMotion>>();
+    mouse_motion.clear();
+    if frame.mouse_motion != [0.0, 0.0] {
+        mouse_motion.send(MouseMotion { delta: Vec2::from(frame.mouse_motion) });
+    }
+    let mut mouse_wheel = world.resource_mut::<Events<MouseWheel>>();
+    mouse_wheel.clear();
+    mouse_wheel.extend(frame.mouse_wheel.iter().cloned());
+    let mut cursor_moved = world.resource_mut::<Events<CursorMoved>>();
+    cursor_moved.clear();
+    if let Some(position) = frame.cursor_position {
+        cursor_moved.send(CursorMoved { id: WindowId::primary(), position: Vec2::from(position) });
+    }
+
+    *world.resource_mut::<JournalMode>() = JournalMode::Replaying(replay);
+}
+
+pub fn update_journal(world: &mut World) {
+    let mode = std::mem::take(&mut *world.resource_mut::<JournalMode>());
+    let result = match mode {
+        JournalMode::Idle => Ok(JournalMode::Idle),
+        JournalMode::StartRecording(dir) => begin_recording(world, dir),
+        JournalMode::Recording(dir, mut journal, mut mouse) => {
+            record_frame(world, &mut journal, &mut mouse);
+            Ok(JournalMode::Recording(dir, journal, mouse))
+        }
+        JournalMode::StartReplay(dir) => begin_replay(world, &dir),
+        JournalMode::Replaying(replay) => Ok(check_frame(world, replay)),
+    };
+
+    match result {
+        Ok(mode) => *world.resource_mut::<JournalMode>() = mode,
+        Err(error) => report_failure(world, SaveOperation::Load, error),
+    }
+}
+
+fn begin_recording(world: &mut World, dir: PathBuf) -> Result<JournalMode, SaveError> {
+    save_to(world, &dir.join(JOURNAL_SAVE))?;
+    let journal = InputJournal {
+        checkpoint_interval: CHECKPOINT_INTERVAL,
+        frames: vec![],
+    };
+    let mut mouse = MouseReaders::default();
+    // Mouse input from before the recording started must not end up in the first frame
+    mouse.skip(world);
+    info!("Recording input to {}", dir.display());
+    Ok(JournalMode::Recording(dir, journal, mouse))
+}
+
+fn record_frame(world: &World, journal: &mut InputJournal, mouse: &mut MouseReaders) {
+    let motion = mouse.motion.iter(world.resource::<Events<MouseMotion>>()).fold(Vec2::ZERO, |sum, motion| sum + motion.delta);
+    let mouse_wheel = mouse.wheel.iter(world.resource::<Events<MouseWheel>>()).cloned().collect();
+    // Panning works off the cursor position, the last one of the frame is all it needs
+    let cursor_position = mouse.cursor.iter(world.resource::<Events<CursorMoved>>())
+        .rfind(|event| event.id == WindowId::primary())
+        .map(|event| event.position.to_array());
+    let frame_number = journal.frames.len() as u32 + 1;
+    journal.frames.push(JournalFrame {
+        delta: world.resource::<Time>().delta(),
+        keys: world.resource::<Input<KeyCode>>().get_pressed().copied().collect(),
+        mouse_buttons: world.resource::<Input<MouseButton>>().get_pressed().copied().collect(),
+        mouse_motion: motion.to_array(),
+        mouse_wheel,
+        cursor_position,
+        checkpoint: frame_number.is_multiple_of(journal.checkpoint_interval).then(|| world_hash(world)),
+    });
+}
+
+fn begin_replay(world: &mut World, dir: &Path) -> Result<JournalMode, SaveError> {
+    let journal = InputJournal::read(dir)?;
+    let scene = read_save(&dir.join(JOURNAL_SAVE), world.resource::<AppTypeRegistry>())?;
+
+    // Spawned right away instead of through a scene bundle, the first replayed frame has to see the loaded world
+    let saved_entities: Vec<Entity> = world.query_filtered::<Entity, With<Save>>().iter(world).collect();
+    for entity in saved_entities {
+        world.despawn(entity);
+    }
+    merge_scene(world, &scene)?;
+
+    info!("Replaying {} frames from {}", journal.frames.len(), dir.display());
+    Ok(JournalMode::Replaying(Box::new(Replay {
+        journal,
+        frame: 0,
+        keys: Input::default(),
+        mouse_buttons: Input::default(),
+        time: world.resource::<Time>().clone(),
+        mismatches: vec![],
+    })))
+}
+
+fn check_frame(world: &mut World, mut replay: Box<Replay>) -> JournalMode {
+    if let Some(expected) = replay.journal.frames[replay.frame].checkpoint {
+        let actual = world_hash(world);
+        if actual != expected {
+            warn!("Replay diverged at frame {}: expected hash {:016x}, got {:016x}", replay.frame, expected, actual);
+            replay.mismatches.push(replay.frame);
+        }
+    }
+
+    replay.frame += 1;
+    if replay.frame < replay.journal.frames.len() {
+        return JournalMode::Replaying(replay);
+    }
+
+    if replay.mismatches.is_empty() {
+        info!("Replay finished, all checkpoints matched");
+    } else {
+        warn!("Replay finished, {} checkpoints diverged", replay.mismatches.len());
+    }
+    world.resource_mut::<Input<KeyCode>>().reset_all();
+    world.resource_mut::<Input<MouseButton>>().reset_all();
+    JournalMode::Idle
+}
+
+fn apply_pressed<T: Copy + Eq + std::hash::Hash + Send + Sync + 'static>(input: &mut Input<T>, pressed: &[T]) {
+    let released: Vec<T> = input.get_pressed().filter(|value| !pressed.contains(value)).copied().collect();
+    for value in released {
+        input.release(value);
+    }
+    for value in pressed {
+        input.press(*value);
+    }
+}
@@ -0,0 +1,140 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use filetime::FileTime;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::error::SaveError;
+use super::storage::write_atomic;
+use super::format::SaveFormat;
+
+const MANIFEST_FILE: &str = "manifest.ron";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ManifestEntry {
+    pub sequence: u64,
+    pub file: String,
+    #[serde(default)]
+    pub checksum: Option<u32>,
+}
+
+// Rotation order lives here instead of in file modification times, so copying or touching
+// a save never changes which one is considered the latest.
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct SaveManifest {
+    pub sequence: u64,
+    pub saves: Vec<ManifestEntry>,
+}
+
+impl SaveManifest {
+    pub fn load(dir: &Path) -> Result<Self, SaveError> {
+        let path = dir.join(MANIFEST_FILE);
+        if !path.is_file() {
+            return Self::rebuild(dir);
+        }
+
+        let manifest = fs::read_to_string(&path)?;
+        match ron::from_str(&manifest) {
+            Ok(manifest) => Ok(manifest),
+            Err(error) => {
+                warn!("Rebuilding corrupt save manifest {}: {}", path.display(), error);
+                Self::rebuild(dir)
+            }
+        }
+    }
+
+    // Without a manifest the only ordering hint left is the file modification time,
+    // so it is used once here and the result is persisted right away.
+    pub fn rebuild(dir: &Path) -> Result<Self, SaveError> {
+        let regex = Regex::new(r"^\d+\.scn\.(ron|bin|bin\.zst)$").unwrap();
+        fs::create_dir_all(dir)?;
+
+        let mut files = vec![];
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let file = match entry.file_name().into_string() {
+                Ok(file) if regex.is_match(&file) => file,
+                _ => continue,
+            };
+            let modified = FileTime::from_last_modification_time(&entry.metadata()?);
+            files.push((modified, save_number(&file), file));
+        }
+        files.sort();
+
+        let mut manifest = Self::default();
+        for (_, _, file) in files {
+            let checksum = read_checksum(dir, &file);
+            manifest.record(file, checksum);
+        }
+        manifest.write(dir)?;
+
+        Ok(manifest)
+    }
+
+    pub fn write(&self, dir: &Path) -> Result<(), SaveError> {
+        let manifest = ron::ser::to_string_pretty(self, Default::default()).map_err(SaveError::Serialize)?;
+        write_atomic(&dir.join(MANIFEST_FILE), manifest.as_bytes())
+    }
+
+    pub fn newest_first(&self) -> impl Iterator<Item=&ManifestEntry> {
+        self.saves.iter().rev()
+    }
+
+    pub fn next_file(&self, amount: u32, format: SaveFormat) -> String {
+        let number = (1..=amount)
+            .find(|number| !self.saves.iter().any(|save| save_number(&save.file) == *number))
+            .or_else(|| self.saves.first().map(|oldest| save_number(&oldest.file)))
+            .unwrap_or(1);
+        format!("{}.{}", number, format.extension())
+    }
+
+    // Returns the files that were rotated out under a different format and are now stale
+    pub fn record(&mut self, file: String, checksum: Option<u32>) -> Vec<String> {
+        let number = save_number(&file);
+        let replaced = self.saves.iter()
+            .filter(|save| save_number(&save.file) == number && save.file != file)
+            .map(|save| save.file.clone())
+            .collect();
+
+        self.sequence += 1;
+        self.saves.retain(|save| save_number(&save.file) != number);
+        self.saves.push(ManifestEntry { sequence: self.sequence, file, checksum });
+
+        replaced
+    }
+
+    pub fn path_of(dir: &Path, entry: &ManifestEntry) -> PathBuf {
+        dir.join(&entry.file)
+    }
+}
+
+// Every save also keeps its checksum next to it, so a rebuilt manifest can still verify it
+pub fn write_checksum(dir: &Path, file: &str, checksum: u32) -> Result<(), SaveError> {
+    write_atomic(&checksum_path(dir, file), checksum.to_string().as_bytes())
+}
+
+pub fn remove_checksum(dir: &Path, file: &str) -> Result<(), SaveError> {
+    match fs::remove_file(checksum_path(dir, file)) {
+        // Saves written before checksums were kept next to them have none
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+fn read_checksum(dir: &Path, file: &str) -> Option<u32> {
+    fs::read_to_string(checksum_path(dir, file)).ok()?.trim().parse().ok()
+}
+
+fn checksum_path(dir: &Path, file: &str) -> PathBuf {
+    dir.join(format!("{}.crc32", file))
+}
+
+fn save_number(file: &str) -> u32 {
+    file.split('.')
+        .next()
+        .and_then(|number| number.parse().ok())
+        .unwrap_or(0)
+}
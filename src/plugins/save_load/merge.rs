@@ -0,0 +1,83 @@
+use bevy::ecs::entity::EntityMap;
+use bevy::ecs::reflect::ReflectMapEntities;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::plugins::components::{Name, Save};
+
+use super::error::SaveError;
+use super::post_load::LoadCompleted;
+
+#[derive(Debug, Clone)]
+pub struct NameCollision {
+    pub name: String,
+    pub existing: Entity,
+    pub loaded: Entity,
+}
+
+#[derive(Debug, Default)]
+pub struct LoadReport {
+    pub spawned: Vec<Entity>,
+    pub collisions: Vec<NameCollision>,
+    pub unmapped_references: Vec<String>,
+}
+
+// Unlike a replacing load this keeps every entity already in the world, so scene ids are
+// remapped to freshly spawned entities and components holding `Entity` references are
+// patched through their `ReflectMapEntities` registration.
+pub fn merge_scene(world: &mut World, scene: &DynamicScene) -> Result<LoadReport, SaveError> {
+    let type_registry = world.resource::<AppTypeRegistry>().clone();
+    let type_registry = type_registry.read();
+
+    let mut reflect_components = vec![];
+    for scene_entity in &scene.entities {
+        for component in &scene_entity.components {
+            let reflect_component = type_registry
+                .get_with_name(component.type_name())
+                .and_then(|registration| registration.data::<ReflectComponent>())
+                .ok_or_else(|| SaveError::UnregisteredType(component.type_name().to_string()))?;
+            reflect_components.push(reflect_component.clone());
+        }
+    }
+
+    let existing_names: HashMap<String, Entity> = world
+        .query_filtered::<(Entity, &Name), With<Save>>()
+        .iter(world)
+        .map(|(entity, name)| (name.0.clone(), entity))
+        .collect();
+
+    let mut report = LoadReport::default();
+    let mut entity_map = EntityMap::default();
+    let mut reflect_components = reflect_components.into_iter();
+    for scene_entity in &scene.entities {
+        let entity = world.spawn_empty().id();
+        entity_map.insert(Entity::from_raw(scene_entity.entity), entity);
+        report.spawned.push(entity);
+
+        for (component, reflect_component) in scene_entity.components.iter().zip(reflect_components.by_ref()) {
+            reflect_component.apply_or_insert(world, entity, &**component);
+        }
+    }
+
+    for registration in type_registry.iter() {
+        if let Some(map_entities) = registration.data::<ReflectMapEntities>() {
+            if let Err(error) = map_entities.map_entities(world, &entity_map) {
+                report.unmapped_references.push(format!("{}: {}", registration.type_name(), error));
+            }
+        }
+    }
+
+    for &loaded in &report.spawned {
+        if let Some(name) = world.get::<Name>(loaded) {
+            if let Some(&existing) = existing_names.get(&name.0) {
+                warn!("Merged entity {:?} has the same name {:?} as {:?}", loaded, name.0, existing);
+                report.collisions.push(NameCollision { name: name.0.clone(), existing, loaded });
+            }
+        }
+    }
+
+    // Merged entities exist right away, there is no scene instance to wait for
+    world.send_event(LoadCompleted { entities: report.spawned.clone() });
+
+    Ok(report)
+}
@@ -0,0 +1,212 @@
+use std::fmt::Formatter;
+
+use bevy::prelude::*;
+use bevy::reflect::serde::TypedReflectDeserializer;
+use bevy::reflect::TypeRegistryInternal;
+use bevy::scene::serde::{SceneDeserializer, SceneSerializer};
+use bevy::scene::{serialize_ron, DynamicEntity};
+use ron::{Map, Value};
+use serde::de::{self, DeserializeSeed, IgnoredAny, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::error::SaveError;
+
+pub type Migration = fn(&mut Value) -> Result<(), SaveError>;
+
+// Entry n upgrades the scene of a version n save to version n + 1.
+const MIGRATIONS: &[Migration] = &[tag_saved_entities];
+
+pub const SAVE_FORMAT_VERSION: u32 = MIGRATIONS.len() as u32;
+
+#[derive(Serialize)]
+struct VersionedScene<'a> {
+    version: u32,
+    scene: SceneSerializer<'a>,
+}
+
+pub fn serialize_versioned(scene: &DynamicScene, type_registry: &AppTypeRegistry) -> Result<String, SaveError> {
+    serialize_ron(VersionedScene {
+        version: SAVE_FORMAT_VERSION,
+        scene: SceneSerializer::new(scene, &type_registry.0),
+    }).map_err(SaveError::Serialize)
+}
+
+// Current saves go straight to the scene deserializer. Only older ones are read into a `ron::Value`
+// for the migrations, it loses enum variant names and large integers on the way.
+pub fn deserialize_versioned(bytes: &[u8], type_registry: &AppTypeRegistry) -> Result<DynamicScene, SaveError> {
+    let type_registry = type_registry.read();
+    let mut deserializer = ron::Deserializer::from_bytes(bytes).map_err(|error| SaveError::Deserialize(error.into()))?;
+    let save = VersionedSceneDeserializer { type_registry: &type_registry }
+        .deserialize(&mut deserializer)
+        .map_err(SaveError::Deserialize)?;
+    deserializer.end().map_err(SaveError::Deserialize)?;
+
+    match save {
+        VersionedSave::Current(scene) => Ok(scene),
+        VersionedSave::Old(version, mut scene) => {
+            migrate(&mut scene, version)?;
+            scene_from_value(scene, &type_registry)
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum Field {
+    Version,
+    Scene,
+    Entities,
+    #[serde(other)]
+    Other,
+}
+
+enum VersionedSave {
+    Current(DynamicScene),
+    Old(u32, Value),
+}
+
+struct VersionedSceneDeserializer<'a> {
+    type_registry: &'a TypeRegistryInternal,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for VersionedSceneDeserializer<'a> {
+    type Value = VersionedSave;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_struct("VersionedScene", &["version", "scene"], self)
+    }
+}
+
+impl<'a, 'de> Visitor<'de> for VersionedSceneDeserializer<'a> {
+    type Value = VersionedSave;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("versioned scene struct")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut version = None;
+        let mut save = None;
+        while let Some(field) = map.next_key()? {
+            match field {
+                Field::Version => version = Some(map.next_value::<u32>()?),
+                // `serialize_versioned` writes the version first, so the scene knows which way to go
+                Field::Scene => save = Some(match version {
+                    Some(SAVE_FORMAT_VERSION) => VersionedSave::Current(map.next_value_seed(SceneDeserializer { type_registry: self.type_registry })?),
+                    Some(version) => VersionedSave::Old(version, map.next_value()?),
+                    None => return Err(de::Error::custom("`version` has to come before `scene`")),
+                }),
+                // Saves written before the format was versioned are a bare scene
+                Field::Entities if version.is_none() => {
+                    let mut scene = Map::new();
+                    scene.insert(key("entities"), map.next_value()?);
+                    save = Some(VersionedSave::Old(0, Value::Map(scene)));
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        save.ok_or_else(|| de::Error::missing_field("scene"))
+    }
+}
+
+pub fn migrate(scene: &mut Value, version: u32) -> Result<(), SaveError> {
+    if version > SAVE_FORMAT_VERSION {
+        return Err(SaveError::UnsupportedVersion(version));
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(scene)?;
+    }
+    Ok(())
+}
+
+fn scene_from_value(scene: Value, type_registry: &TypeRegistryInternal) -> Result<DynamicScene, SaveError> {
+    let mut scene = into_map(scene)?;
+    let entities = into_map(scene.remove(&key("entities")).ok_or_else(|| invalid("missing field `entities`"))?)?;
+
+    let mut dynamic_entities = vec![];
+    for (id, entity) in entities.iter() {
+        let mut entity = into_map(entity.clone())?;
+        let components = into_map(entity.remove(&key("components")).ok_or_else(|| invalid("missing field `components`"))?)?;
+
+        let mut dynamic_components = vec![];
+        for (type_name, component) in components.iter() {
+            let type_name: String = type_name.clone().into_rust().map_err(SaveError::Deserialize)?;
+            let registration = type_registry
+                .get_with_name(&type_name)
+                .ok_or_else(|| invalid(&format!("no registration found for `{}`", type_name)))?;
+            // Fieldless structs like `Save` are written as `()`, reflection only reads them back as an empty struct
+            let component = match component {
+                Value::Unit => Value::Map(Map::new()),
+                component => component.clone(),
+            };
+            dynamic_components.push(TypedReflectDeserializer::new(registration, type_registry)
+                .deserialize(component)
+                .map_err(SaveError::Deserialize)?);
+        }
+
+        dynamic_entities.push(DynamicEntity {
+            entity: id.clone().into_rust().map_err(SaveError::Deserialize)?,
+            components: dynamic_components,
+        });
+    }
+
+    Ok(DynamicScene { entities: dynamic_entities })
+}
+
+// Version 0 saves came from `DynamicScene::from_world`, so they hold every reflected entity
+// (scene roots with transforms included) and none of them carry `Save` yet.
+fn tag_saved_entities(scene: &mut Value) -> Result<(), SaveError> {
+    const GAME_COMPONENTS: [&str; 2] = [
+        "game::plugins::components::components::Body",
+        "game::plugins::components::components::Name",
+    ];
+    const SAVE: &str = "game::plugins::components::components::Save";
+
+    let mut scene_map = into_map(std::mem::replace(scene, Value::Unit))?;
+    let entities = into_map(scene_map.remove(&key("entities")).ok_or_else(|| invalid("missing field `entities`"))?)?;
+
+    let mut tagged = Map::new();
+    for (id, entity) in entities.iter() {
+        let mut entity = into_map(entity.clone())?;
+        let mut components = match entity.remove(&key("components")) {
+            Some(Value::Map(components)) => components,
+            _ => continue,
+        };
+
+        let mut kept = Map::new();
+        for type_name in GAME_COMPONENTS {
+            if let Some(component) = components.remove(&key(type_name)) {
+                kept.insert(key(type_name), component);
+            }
+        }
+        if kept.is_empty() {
+            continue;
+        }
+        kept.insert(key(SAVE), Value::Unit);
+
+        entity.insert(key("components"), Value::Map(kept));
+        tagged.insert(id.clone(), Value::Map(entity));
+    }
+
+    scene_map.insert(key("entities"), Value::Map(tagged));
+    *scene = Value::Map(scene_map);
+
+    Ok(())
+}
+
+fn into_map(value: Value) -> Result<Map, SaveError> {
+    match value {
+        Value::Map(map) => Ok(map),
+        value => Err(invalid(&format!("expected a struct, found {:?}", value))),
+    }
+}
+
+fn key(name: &str) -> Value {
+    Value::String(name.to_string())
+}
+
+fn invalid(message: &str) -> SaveError {
+    SaveError::Deserialize(ron::Error::Message(message.to_string()))
+}
@@ -0,0 +1,69 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::plugins::components::{PersistentId, Save};
+
+#[derive(Resource, Default)]
+pub struct PersistentIndex {
+    entities: HashMap<PersistentId, Entity>,
+    ids: HashMap<Entity, PersistentId>,
+}
+
+impl PersistentIndex {
+    pub fn get(&self, id: PersistentId) -> Option<Entity> {
+        self.entities.get(&id).copied()
+    }
+
+    pub fn id_of(&self, entity: Entity) -> Option<PersistentId> {
+        self.ids.get(&entity).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+pub fn assign_persistent_ids(mut commands: Commands, unassigned: Query<Entity, (With<Save>, Without<PersistentId>)>) {
+    for entity in &unassigned {
+        commands.entity(entity).insert(PersistentId::new());
+    }
+}
+
+// Loaded entities keep the id they were saved with, only a second copy of the same entity
+// (for example the same save merged twice) gets a new one.
+pub fn index_persistent_ids(
+    mut commands: Commands,
+    mut index: ResMut<PersistentIndex>,
+    changed: Query<(Entity, &PersistentId), Changed<PersistentId>>,
+    removed: RemovedComponents<PersistentId>,
+) {
+    for entity in removed.iter() {
+        if let Some(id) = index.ids.remove(&entity) {
+            if index.entities.get(&id) == Some(&entity) {
+                index.entities.remove(&id);
+            }
+        }
+    }
+
+    for (entity, &id) in &changed {
+        if let Some(previous) = index.ids.insert(entity, id) {
+            if index.entities.get(&previous) == Some(&entity) {
+                index.entities.remove(&previous);
+            }
+        }
+        match index.entities.get(&id) {
+            Some(&existing) if existing != entity => {
+                warn!("{:?} has the same persistent id {:?} as {:?}, assigning a new one", entity, id, existing);
+                index.ids.remove(&entity);
+                commands.entity(entity).insert(PersistentId::new());
+            }
+            _ => {
+                index.entities.insert(id, entity);
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+use bevy::scene::{SceneInstance, SceneSpawner};
+
+use crate::plugins::components::Body;
+
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SaveLoadSet {
+    PostLoad,
+}
+
+pub struct LoadCompleted {
+    pub entities: Vec<Entity>,
+}
+
+// Marks the `DynamicSceneBundle` of a load until the scene spawner has instantiated it
+#[derive(Component)]
+pub struct PendingLoad;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Validation {
+    Valid,
+    Repaired(String),
+    Rejected(String),
+}
+
+// Runs on every loaded entity with the component, rejected entities are despawned
+pub trait LoadValidator: Component {
+    fn validate(&mut self) -> Validation;
+}
+
+pub trait AddLoadValidator {
+    fn add_load_validator<T: LoadValidator>(&mut self) -> &mut Self;
+}
+
+impl AddLoadValidator for App {
+    fn add_load_validator<T: LoadValidator>(&mut self) -> &mut Self {
+        self.add_system(validate_loaded::<T>.label(SaveLoadSet::PostLoad).after(detect_completed_loads))
+    }
+}
+
+pub fn detect_completed_loads(
+    mut commands: Commands,
+    scene_spawner: Res<SceneSpawner>,
+    pending: Query<(Entity, &SceneInstance), With<PendingLoad>>,
+    mut completed: EventWriter<LoadCompleted>,
+) {
+    for (root, instance) in &pending {
+        if scene_spawner.instance_is_ready(**instance) {
+            commands.entity(root).remove::<PendingLoad>();
+            completed.send(LoadCompleted { entities: scene_spawner.iter_instance_entities(**instance).collect() });
+        }
+    }
+}
+
+fn validate_loaded<T: LoadValidator>(
+    mut commands: Commands,
+    mut completed: EventReader<LoadCompleted>,
+    mut components: Query<&mut T>,
+) {
+    for load in completed.iter() {
+        for &entity in &load.entities {
+            let mut component = match components.get_mut(entity) {
+                Ok(component) => component,
+                Err(_) => continue,
+            };
+            match component.validate() {
+                Validation::Valid => {}
+                Validation::Repaired(reason) => warn!("Repaired loaded {:?}: {}", entity, reason),
+                Validation::Rejected(reason) => {
+                    warn!("Rejected loaded {:?}: {}", entity, reason);
+                    commands.entity(entity).despawn();
+                }
+            }
+        }
+    }
+}
+
+impl LoadValidator for Body {
+    fn validate(&mut self) -> Validation {
+        let vertebrae_count = self.spine.len();
+        self.spine.retain(|vertebrae| vertebrae.position.iter().all(|coordinate| coordinate.is_finite()));
+        let dropped = vertebrae_count - self.spine.len();
+        if self.spine.is_empty() {
+            return Validation::Rejected("body has no spine".to_string());
+        }
+
+        let mut clamped = 0;
+        for vertebrae in &mut self.spine {
+            for channel in &mut vertebrae.color {
+                let repaired = if channel.is_nan() { 0.0 } else { channel.clamp(0.0, 1.0) };
+                if repaired != *channel {
+                    *channel = repaired;
+                    clamped += 1;
+                }
+            }
+        }
+
+        if dropped == 0 && clamped == 0 {
+            Validation::Valid
+        } else {
+            Validation::Repaired(format!("dropped {} non-finite vertebrae, clamped {} color channels", dropped, clamped))
+        }
+    }
+}
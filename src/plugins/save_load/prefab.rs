@@ -0,0 +1,125 @@
+use std::path::Path;
+
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::ecs::system::Command;
+use bevy::ecs::world::EntityMut;
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use serde::{Deserialize, Serialize};
+
+use crate::plugins::components::{Body, Name, Save, Vertebrae};
+
+use super::error::SaveError;
+use super::storage::write_atomic;
+
+pub const PREFAB_EXTENSION: &str = "body.ron";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct PrefabVertebrae {
+    pub position: [f32; 4],
+    pub color: [f32; 4],
+}
+
+#[derive(Serialize, Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "ad512404-8492-4928-a302-ffb1b95eb462"]
+pub struct BodyPrefab {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub spine: Vec<PrefabVertebrae>,
+}
+
+impl BodyPrefab {
+    pub fn from_body(body: &Body, name: Option<&Name>) -> Self {
+        Self {
+            name: name.map(|name| name.0.clone()),
+            spine: body.spine.iter()
+                .map(|vertebrae| PrefabVertebrae { position: vertebrae.position, color: vertebrae.color })
+                .collect(),
+        }
+    }
+
+    pub fn to_body(&self, offset: Vec2) -> Body {
+        Body {
+            spine: self.spine.iter()
+                .map(|vertebrae| {
+                    let [x, y, z, w] = vertebrae.position;
+                    Vertebrae { position: [x + offset.x, y + offset.y, z, w], color: vertebrae.color }
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BodyPrefabLoader;
+
+impl AssetLoader for BodyPrefabLoader {
+    fn load<'a>(&'a self, bytes: &'a [u8], load_context: &'a mut LoadContext) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let prefab: BodyPrefab = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(prefab));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[PREFAB_EXTENSION]
+    }
+}
+
+// Stands in for the body until its prefab has finished loading
+#[derive(Component)]
+pub struct PendingBody {
+    pub handle: Handle<BodyPrefab>,
+    pub offset: Vec2,
+}
+
+pub struct SpawnBody {
+    pub handle: Handle<BodyPrefab>,
+    pub offset: Vec2,
+}
+
+impl Command for SpawnBody {
+    fn write(self, world: &mut World) {
+        let prefab = world.resource::<Assets<BodyPrefab>>().get(&self.handle).cloned();
+        match prefab {
+            Some(prefab) => {
+                spawn_prefab(&mut world.spawn_empty(), &prefab, self.offset);
+            }
+            None => {
+                world.spawn(PendingBody { handle: self.handle, offset: self.offset });
+            }
+        }
+    }
+}
+
+pub fn spawn_pending_bodies(world: &mut World) {
+    let mut query = world.query::<(Entity, &PendingBody)>();
+    let pending: Vec<(Entity, BodyPrefab, Vec2)> = {
+        let prefabs = world.resource::<Assets<BodyPrefab>>();
+        query.iter(world)
+            .filter_map(|(entity, pending)| prefabs.get(&pending.handle).map(|prefab| (entity, prefab.clone(), pending.offset)))
+            .collect()
+    };
+
+    for (entity, prefab, offset) in pending {
+        let mut entity = world.entity_mut(entity);
+        entity.remove::<PendingBody>();
+        spawn_prefab(&mut entity, &prefab, offset);
+    }
+}
+
+fn spawn_prefab(entity: &mut EntityMut, prefab: &BodyPrefab, offset: Vec2) {
+    entity.insert((prefab.to_body(offset), Save));
+    if let Some(name) = &prefab.name {
+        entity.insert(Name(name.clone()));
+    }
+}
+
+pub fn export_body(world: &World, entity: Entity, path: &Path) -> Result<(), SaveError> {
+    let body = world.get::<Body>(entity).ok_or(SaveError::NotABody(entity))?;
+    let prefab = BodyPrefab::from_body(body, world.get::<Name>(entity));
+    let prefab = ron::ser::to_string_pretty(&prefab, Default::default()).map_err(SaveError::Serialize)?;
+    write_atomic(path, prefab.as_bytes())
+}
@@ -0,0 +1,52 @@
+use std::any::TypeId;
+
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use bevy::scene::DynamicEntity;
+
+use crate::plugins::components::Save;
+
+#[derive(Resource, Default)]
+pub struct SaveComponents {
+    allowed: HashSet<TypeId>,
+}
+
+impl SaveComponents {
+    pub fn with<T: Component>(mut self) -> Self {
+        self.allowed.insert(TypeId::of::<T>());
+        self
+    }
+
+    pub fn contains(&self, type_id: TypeId) -> bool {
+        self.allowed.contains(&type_id)
+    }
+}
+
+// Only entities tagged with `Save` and only components on the allow-list end up in the scene,
+// so window, input and render state never get written to disk.
+pub fn build_save_scene(world: &World) -> DynamicScene {
+    let type_registry = world.resource::<AppTypeRegistry>().read();
+    let save_components = world.resource::<SaveComponents>();
+
+    let entities = world.iter_entities()
+        .filter(|entity| world.get::<Save>(*entity).is_some())
+        .map(|entity| {
+            let mut components = vec![];
+            for component_id in world.entity(entity).archetype().components() {
+                let reflect_component = world.components()
+                    .get_info(component_id)
+                    .and_then(|info| info.type_id())
+                    .filter(|type_id| save_components.contains(*type_id))
+                    .and_then(|type_id| type_registry.get(type_id))
+                    .and_then(|registration| registration.data::<ReflectComponent>());
+
+                if let Some(component) = reflect_component.and_then(|reflect| reflect.reflect(world, entity)) {
+                    components.push(component.clone_value());
+                }
+            }
+            DynamicEntity { entity: entity.index(), components }
+        })
+        .collect();
+
+    DynamicScene { entities }
+}
@@ -0,0 +1,305 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::autosave::AUTOSAVE_SLOT;
+use super::background::SaveStage;
+use super::error::SaveError;
+use super::format::{decode, encode, SaveFormat};
+use super::journal::JOURNAL_DIR;
+use super::manifest::{remove_checksum, write_checksum, SaveManifest};
+use super::merge::{merge_scene, LoadReport};
+use super::scene::build_save_scene;
+use super::storage::{checksum, read_verified, write_atomic};
+use super::thumbnail::{Thumbnail, ThumbnailCapture};
+use super::systems::spawn_save;
+
+pub const QUICK_SLOT: &str = "quick";
+
+const QUICK_SAVE_AMOUNT: u32 = 5;
+const RESERVED_SLOTS: [&str; 2] = [QUICK_SLOT, AUTOSAVE_SLOT];
+const METADATA_FILE: &str = "slot.ron";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SlotMetadata {
+    pub name: String,
+    pub created: u64,
+    pub modified: u64,
+    pub play_time: Duration,
+    pub entity_count: usize,
+    pub game_version: String,
+    #[serde(default)]
+    pub thumbnail: Option<String>,
+}
+
+#[derive(Resource)]
+pub struct SaveSlots {
+    pub format: SaveFormat,
+    dir: PathBuf,
+    play_time_base: Duration,
+    session_start: Duration,
+}
+
+impl SaveSlots {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            format: SaveFormat::Ron,
+            dir: dir.into(),
+            play_time_base: Duration::ZERO,
+            session_start: Duration::ZERO,
+        }
+    }
+
+    pub fn slot_dir(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    pub fn list(&self) -> Result<Vec<SlotMetadata>, SaveError> {
+        fs::create_dir_all(&self.dir)?;
+
+        let mut slots = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if !entry.path().join(METADATA_FILE).is_file() {
+                continue;
+            }
+            // One damaged slot should not hide all the others
+            match read_metadata(&entry.path()) {
+                Ok(metadata) => slots.push(metadata),
+                Err(error) => warn!("Skipping slot {}: {}", entry.path().display(), error),
+            }
+        }
+        slots.sort_by_key(|slot| Reverse(slot.modified));
+
+        Ok(slots)
+    }
+
+    pub fn metadata(&self, name: &str) -> Result<SlotMetadata, SaveError> {
+        let slot_dir = self.slot_dir(name);
+        if !slot_dir.join(METADATA_FILE).is_file() {
+            return Err(SaveError::SlotNotFound(name.to_string()));
+        }
+        read_metadata(&slot_dir)
+    }
+
+    pub fn create(&self, world: &World, name: &str) -> Result<SlotMetadata, SaveError> {
+        validate_name(name)?;
+        check_not_reserved(name)?;
+        if self.slot_dir(name).exists() {
+            return Err(SaveError::SlotExists(name.to_string()));
+        }
+        self.snapshot(world, name, rotation(name))?.write(|_| {})
+    }
+
+    pub fn overwrite(&self, world: &World, name: &str) -> Result<SlotMetadata, SaveError> {
+        self.metadata(name)?;
+        self.snapshot(world, name, rotation(name))?.write(|_| {})
+    }
+
+    pub fn save(&self, world: &World, name: &str) -> Result<SlotMetadata, SaveError> {
+        self.save_rotating(world, name, rotation(name))
+    }
+
+    pub fn save_rotating(&self, world: &World, name: &str, rotation: u32) -> Result<SlotMetadata, SaveError> {
+        self.snapshot(world, name, rotation)?.write(|_| {})
+    }
+
+    pub fn snapshot(&self, world: &World, name: &str, rotation: u32) -> Result<SlotSnapshot, SaveError> {
+        let previous = match self.metadata(name) {
+            Ok(previous) => Some(previous),
+            Err(SaveError::SlotNotFound(_)) => {
+                validate_name(name)?;
+                None
+            }
+            Err(error) => return Err(error),
+        };
+
+        Ok(SlotSnapshot {
+            name: name.to_string(),
+            slot_dir: self.slot_dir(name),
+            rotation,
+            format: self.format,
+            scene: build_save_scene(world),
+            type_registry: world.resource::<AppTypeRegistry>().clone(),
+            previous,
+            play_time: self.play_time(world.resource::<Time>()),
+            thumbnail: world.get_resource::<ThumbnailCapture>().and_then(|capture| (capture.0)(world)),
+        })
+    }
+
+    pub fn rename(&self, from: &str, to: &str) -> Result<SlotMetadata, SaveError> {
+        validate_name(to)?;
+        check_not_reserved(from)?;
+        check_not_reserved(to)?;
+        let mut metadata = self.metadata(from)?;
+        if self.slot_dir(to).exists() {
+            return Err(SaveError::SlotExists(to.to_string()));
+        }
+
+        fs::rename(self.slot_dir(from), self.slot_dir(to))?;
+        metadata.name = to.to_string();
+        write_metadata(&self.slot_dir(to), &metadata)?;
+
+        Ok(metadata)
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), SaveError> {
+        check_not_reserved(name)?;
+        self.metadata(name)?;
+        fs::remove_dir_all(self.slot_dir(name))?;
+        Ok(())
+    }
+
+    pub fn read_scene(&self, name: &str, type_registry: &AppTypeRegistry) -> Result<DynamicScene, SaveError> {
+        let slot_dir = self.slot_dir(name);
+        let manifest = SaveManifest::load(&slot_dir)?;
+        read_newest_intact(&slot_dir, &manifest, type_registry)
+    }
+
+    pub fn play_time(&self, time: &Time) -> Duration {
+        self.play_time_base + time.elapsed().saturating_sub(self.session_start)
+    }
+}
+
+pub struct SlotSnapshot {
+    pub name: String,
+    slot_dir: PathBuf,
+    rotation: u32,
+    format: SaveFormat,
+    scene: DynamicScene,
+    type_registry: AppTypeRegistry,
+    previous: Option<SlotMetadata>,
+    play_time: Duration,
+    thumbnail: Option<Thumbnail>,
+}
+
+impl SlotSnapshot {
+    // Needs no access to the world, so it can run on a task pool while the game keeps going
+    pub fn write(self, mut on_stage: impl FnMut(SaveStage)) -> Result<SlotMetadata, SaveError> {
+        let mut manifest = SaveManifest::load(&self.slot_dir)?;
+        let file = manifest.next_file(self.rotation, self.format);
+
+        on_stage(SaveStage::Encoding);
+        let bytes = encode(&self.scene, &self.type_registry, self.format)?;
+
+        on_stage(SaveStage::Writing);
+        let checksum = checksum(&bytes);
+        write_atomic(&self.slot_dir.join(&file), &bytes)?;
+        write_checksum(&self.slot_dir, &file, checksum)?;
+        // Named after the save it shows, so rotating a save out replaces its thumbnail as well
+        let thumbnail = match &self.thumbnail {
+            Some(thumbnail) => {
+                let thumbnail_file = format!("{}.png", file.split('.').next().unwrap_or_default());
+                write_atomic(&self.slot_dir.join(&thumbnail_file), &thumbnail.encode_png()?)?;
+                Some(thumbnail_file)
+            }
+            None => None,
+        };
+        let replaced = manifest.record(file, Some(checksum));
+        manifest.write(&self.slot_dir)?;
+        for file in replaced {
+            fs::remove_file(self.slot_dir.join(&file))?;
+            remove_checksum(&self.slot_dir, &file)?;
+        }
+
+        let now = unix_time();
+        let metadata = SlotMetadata {
+            name: self.name,
+            created: self.previous.map_or(now, |previous| previous.created),
+            modified: now,
+            play_time: self.play_time,
+            entity_count: self.scene.entities.len(),
+            game_version: env!("CARGO_PKG_VERSION").to_string(),
+            thumbnail,
+        };
+        write_metadata(&self.slot_dir, &metadata)?;
+
+        Ok(metadata)
+    }
+}
+
+pub fn load_slot(world: &mut World, name: &str) -> Result<SlotMetadata, SaveError> {
+    let (metadata, scene) = read_slot(world, name)?;
+
+    spawn_save(world, scene);
+
+    let session_start = world.resource::<Time>().elapsed();
+    let mut slots = world.resource_mut::<SaveSlots>();
+    slots.play_time_base = metadata.play_time;
+    slots.session_start = session_start;
+
+    Ok(metadata)
+}
+
+// Merged saves become part of the running session, so play time keeps counting from where it was
+pub fn merge_slot(world: &mut World, name: &str) -> Result<LoadReport, SaveError> {
+    let (_, scene) = read_slot(world, name)?;
+    merge_scene(world, &scene)
+}
+
+fn read_slot(world: &World, name: &str) -> Result<(SlotMetadata, DynamicScene), SaveError> {
+    let slots = world.resource::<SaveSlots>();
+    let metadata = slots.metadata(name)?;
+    Ok((metadata, slots.read_scene(name, world.resource::<AppTypeRegistry>())?))
+}
+
+// A save whose checksum no longer matches was cut short or damaged, so the previous one
+// in the rotation is used instead. Saves without a checksum are only caught once they fail to decode.
+fn read_newest_intact(slot_dir: &Path, manifest: &SaveManifest, type_registry: &AppTypeRegistry) -> Result<DynamicScene, SaveError> {
+    for entry in manifest.newest_first() {
+        let path = SaveManifest::path_of(slot_dir, entry);
+        let scene = read_verified(&path, entry.checksum)
+            .and_then(|bytes| decode(&bytes, type_registry, SaveFormat::from_path(&path)?));
+        match scene {
+            Ok(scene) => return Ok(scene),
+            Err(error) => warn!("Skipping save {}: {}", entry.file, error),
+        }
+    }
+    Err(SaveError::NoSaveFound)
+}
+
+pub fn rotation(name: &str) -> u32 {
+    if name == QUICK_SLOT { QUICK_SAVE_AMOUNT } else { 1 }
+}
+
+fn validate_name(name: &str) -> Result<(), SaveError> {
+    // The input journal lives next to the slots, case insensitive file systems would share it with "Journal" too
+    if name.eq_ignore_ascii_case(JOURNAL_DIR) {
+        return Err(SaveError::ReservedSlot(name.to_string()));
+    }
+    let regex = Regex::new(r"^[\w\- ]+$").unwrap();
+    if regex.is_match(name) && name.trim() == name {
+        Ok(())
+    } else {
+        Err(SaveError::InvalidSlotName(name.to_string()))
+    }
+}
+
+// Quick saves and autosaves are written by the game itself, only the player's own slots are managed by name
+fn check_not_reserved(name: &str) -> Result<(), SaveError> {
+    if RESERVED_SLOTS.contains(&name) {
+        return Err(SaveError::ReservedSlot(name.to_string()));
+    }
+    Ok(())
+}
+
+fn read_metadata(slot_dir: &Path) -> Result<SlotMetadata, SaveError> {
+    let metadata = fs::read_to_string(slot_dir.join(METADATA_FILE))?;
+    ron::from_str(&metadata).map_err(|error| SaveError::Deserialize(error.into()))
+}
+
+fn write_metadata(slot_dir: &Path, metadata: &SlotMetadata) -> Result<(), SaveError> {
+    let metadata = ron::ser::to_string_pretty(metadata, Default::default()).map_err(SaveError::Serialize)?;
+    write_atomic(&slot_dir.join(METADATA_FILE), metadata.as_bytes())
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
@@ -0,0 +1,140 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use super::error::{SaveError, SaveOperation};
+use super::format::{decode, encode, SaveFormat};
+use super::scene::build_save_scene;
+use super::slots::SaveSlots;
+use super::systems::{report_failure, spawn_save};
+
+struct Snapshot {
+    format: SaveFormat,
+    bytes: Vec<u8>,
+}
+
+#[derive(Resource)]
+pub struct SnapshotHistory {
+    pub capacity: usize,
+    pub interval: Option<u32>,
+    snapshots: VecDeque<Snapshot>,
+    cursor: usize,
+    ticks: u32,
+}
+
+impl Default for SnapshotHistory {
+    fn default() -> Self {
+        Self {
+            capacity: 32,
+            interval: None,
+            snapshots: VecDeque::new(),
+            cursor: 0,
+            ticks: 0,
+        }
+    }
+}
+
+impl SnapshotHistory {
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    pub fn can_rewind(&self) -> bool {
+        self.cursor > 0
+    }
+
+    pub fn can_step_forward(&self) -> bool {
+        self.cursor + 1 < self.snapshots.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.cursor = 0;
+    }
+
+    // Capturing after a rewind drops the snapshots that could have been stepped forward to
+    fn push(&mut self, snapshot: Snapshot) {
+        self.snapshots.truncate(if self.snapshots.is_empty() { 0 } else { self.cursor + 1 });
+        self.snapshots.push_back(snapshot);
+        while self.snapshots.len() > self.capacity.max(1) {
+            self.snapshots.pop_front();
+        }
+        self.cursor = self.snapshots.len() - 1;
+        self.ticks = 0;
+    }
+}
+
+// Snapshots are encoded exactly like a disk save in the slot format, only kept in memory
+pub fn capture_snapshot(world: &mut World) -> Result<(), SaveError> {
+    let format = world.resource::<SaveSlots>().format;
+    let bytes = encode(&build_save_scene(world), world.resource::<AppTypeRegistry>(), format)?;
+    world.resource_mut::<SnapshotHistory>().push(Snapshot { format, bytes });
+    Ok(())
+}
+
+pub fn rewind(world: &mut World) -> Result<bool, SaveError> {
+    if !world.resource::<SnapshotHistory>().can_rewind() {
+        return Ok(false);
+    }
+    let cursor = world.resource::<SnapshotHistory>().cursor - 1;
+    restore(world, cursor)
+}
+
+pub fn step_forward(world: &mut World) -> Result<bool, SaveError> {
+    if !world.resource::<SnapshotHistory>().can_step_forward() {
+        return Ok(false);
+    }
+    let cursor = world.resource::<SnapshotHistory>().cursor + 1;
+    restore(world, cursor)
+}
+
+fn restore(world: &mut World, cursor: usize) -> Result<bool, SaveError> {
+    let scene = {
+        let history = world.resource::<SnapshotHistory>();
+        let snapshot = &history.snapshots[cursor];
+        decode(&snapshot.bytes, world.resource::<AppTypeRegistry>(), snapshot.format)?
+    };
+    spawn_save(world, scene);
+
+    let mut history = world.resource_mut::<SnapshotHistory>();
+    history.cursor = cursor;
+    history.ticks = 0;
+    Ok(true)
+}
+
+pub fn snapshot_history(world: &mut World) {
+    let (capture, rewinding, stepping) = {
+        let input = world.resource::<Input<KeyCode>>();
+        let control = input.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+        let shift = input.any_pressed([KeyCode::LShift, KeyCode::RShift]);
+        (
+            input.just_released(KeyCode::F6),
+            control && !shift && input.just_pressed(KeyCode::Z),
+            control && (input.just_pressed(KeyCode::Y) || shift && input.just_pressed(KeyCode::Z)),
+        )
+    };
+
+    let interval_elapsed = {
+        let mut history = world.resource_mut::<SnapshotHistory>();
+        history.ticks += 1;
+        history.interval.is_some_and(|interval| history.ticks >= interval)
+    };
+
+    let (operation, result) = if rewinding {
+        (SaveOperation::Load, rewind(world).map(|rewound| if rewound { info!("Rewound") }))
+    } else if stepping {
+        (SaveOperation::Load, step_forward(world).map(|stepped| if stepped { info!("Stepped forward") }))
+    } else if capture || interval_elapsed {
+        (SaveOperation::Save, capture_snapshot(world))
+    } else {
+        return;
+    };
+
+    if let Err(error) = result {
+        report_failure(world, operation, error);
+    }
+}
@@ -0,0 +1,53 @@
+use std::fs;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::error::SaveError;
+
+// Writes go to a temporary sibling first and are renamed over the target only once they are
+// on disk, so a crash mid-write can never leave a truncated file under the real name.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), SaveError> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(dir)?;
+
+    let temporary = temporary_path(path);
+    let mut file = File::create(&temporary)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&temporary, path)?;
+    sync_dir(dir)?;
+
+    Ok(())
+}
+
+pub fn read_verified(path: &Path, expected: Option<u32>) -> Result<Vec<u8>, SaveError> {
+    let bytes = fs::read(path)?;
+    match expected {
+        Some(expected) if expected != checksum(&bytes) => Err(SaveError::ChecksumMismatch(path.to_path_buf())),
+        _ => Ok(bytes),
+    }
+}
+
+pub fn checksum(bytes: &[u8]) -> u32 {
+    crc32fast::hash(bytes)
+}
+
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".tmp");
+    path.with_file_name(file_name)
+}
+
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> Result<(), SaveError> {
+    File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_: &Path) -> Result<(), SaveError> {
+    Ok(())
+}
@@ -1,84 +1,96 @@
 use std::fs;
-use std::fs::{DirEntry, File};
-use std::io::Write;
+use std::path::Path;
 
 use bevy::prelude::*;
-use bevy::reflect::TypeRegistryArc;
 
-use filetime::FileTime;
-use regex::Regex;
 use crate::plugins::components::Save;
 
+use super::background::save_in_background;
+use super::error::{SaveError, SaveLoadFailed, SaveOperation};
+use super::format::{decode, encode, SaveFormat};
+use super::scene::build_save_scene;
+use super::merge::{merge_scene, LoadReport};
+use super::post_load::PendingLoad;
+use super::slots::{load_slot, merge_slot, rotation, QUICK_SLOT};
+use super::storage::{checksum, write_atomic};
 
-const SAVE_FILE_AMOUNT: u32 = 5;
 
-pub fn quick_save_game(
-    keyboard_input: Res<Input<KeyCode>>,
-    world: &World,
-) {
-    if keyboard_input.just_released(KeyCode::F5) {
-        let app_type_registry = world.resource::<AppTypeRegistry>();
+pub fn quick_save_game(world: &mut World) {
+    if world.resource::<Input<KeyCode>>().just_released(KeyCode::F5) {
+        if let Err(error) = save_in_background(world, QUICK_SLOT, rotation(QUICK_SLOT)) {
+            report_failure(world, SaveOperation::Save, error);
+        }
+    }
+}
 
-        let scene = DynamicScene::from_world(world, app_type_registry);
+pub fn quick_load_game(world: &mut World) {
+    let input = world.resource::<Input<KeyCode>>();
+    if !input.just_released(KeyCode::F9) {
+        return;
+    }
 
-        let serialized_scene = scene.serialize_ron(app_type_registry as &TypeRegistryArc).unwrap();
-        let last_file = get_last_save();
+    if input.any_pressed([KeyCode::LShift, KeyCode::RShift]) {
+        match merge_slot(world, QUICK_SLOT) {
+            Ok(report) => info!("Merged {} entities", report.spawned.len()),
+            Err(error) => report_failure(world, SaveOperation::Load, error),
+        }
+    } else {
+        match load_slot(world, QUICK_SLOT) {
+            Ok(_) => info!("Loaded"),
+            Err(error) => report_failure(world, SaveOperation::Load, error),
+        }
+    }
+}
 
-        let file_name = match last_file {
-            None => { "0.scn.ron".to_string() }
-            Some(last_file) => { last_file.file_name().into_string().unwrap() }
-        };
+pub struct SavedFile {
+    pub entity_count: usize,
+    pub checksum: u32,
+}
 
-        let mut number = file_name.split_at(file_name.len() - 8).0.parse::<i32>().unwrap() + 1;
-        if number > SAVE_FILE_AMOUNT as i32 {
-            number = 1
-        }
+pub fn save_to(world: &World, path: &Path) -> Result<SavedFile, SaveError> {
+    let app_type_registry = world.resource::<AppTypeRegistry>();
 
-        let mut file = File::create("assets/saves/".to_string() + &number.to_string() + ".scn.ron").unwrap();
-        file.write_all((serialized_scene).as_bytes()).unwrap();
+    let scene = build_save_scene(world);
 
-        info!("Saved")
-    }
-}
+    let serialized_scene = encode(&scene, app_type_registry, SaveFormat::from_path(path)?)?;
 
-pub fn quick_load_game(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut commands: Commands,
-    query: Query<Entity, &Save>,
-    asset_server: Res<AssetServer>,
-) {
-    if keyboard_input.just_released(KeyCode::F9) {
-        let last_file = get_last_save();
-
-        for entity in query.iter() {
-            commands.entity(entity).despawn();
-        }
+    write_atomic(path, &serialized_scene)?;
 
-        if last_file.is_none() {
-            warn!("No save file found");
-            return;
-        }
+    Ok(SavedFile {
+        entity_count: scene.entities.len(),
+        checksum: checksum(&serialized_scene),
+    })
+}
 
-        let x = last_file.unwrap().path();
+pub fn load_from(world: &mut World, path: &Path) -> Result<(), SaveError> {
+    let scene = read_save(path, world.resource::<AppTypeRegistry>())?;
+    spawn_save(world, scene);
+    Ok(())
+}
 
-        let path = x.to_str().unwrap().split_at(7).1;
+pub fn merge_from(world: &mut World, path: &Path) -> Result<LoadReport, SaveError> {
+    let scene = read_save(path, world.resource::<AppTypeRegistry>())?;
+    merge_scene(world, &scene)
+}
 
-        commands.spawn(DynamicSceneBundle {
-            // Scenes are loaded just like any other asset.
-            scene: asset_server.load(path),
-            ..default()
-        });
+pub fn spawn_save(world: &mut World, scene: DynamicScene) {
+    let saved_entities: Vec<Entity> = world.query_filtered::<Entity, With<Save>>().iter(world).collect();
+    for entity in saved_entities {
+        world.despawn(entity);
     }
+
+    let scene = world.resource_mut::<Assets<DynamicScene>>().add(scene);
+    world.spawn((DynamicSceneBundle {
+        scene,
+        ..default()
+    }, PendingLoad));
 }
 
-fn get_last_save() -> Option<DirEntry> {
-    let regex = Regex::new(r"^\d*\.scn\.ron$").unwrap();
-    fs::create_dir_all("assets/saves").expect("Failed to create dir");
-    fs::read_dir("assets/saves").expect("Failed to read dir").filter(|result| match result {
-        Ok(_) => true,
-        Err(_) => false
-    })
-        .map(|x| x.unwrap())
-        .filter(|file| regex.is_match(file.file_name().to_str().expect("Bad filename")))
-        .max_by_key(|save_file| FileTime::from_last_modification_time(&save_file.metadata().unwrap()))
-}
\ No newline at end of file
+pub fn read_save(path: &Path, type_registry: &AppTypeRegistry) -> Result<DynamicScene, SaveError> {
+    decode(&fs::read(path)?, type_registry, SaveFormat::from_path(path)?)
+}
+
+pub fn report_failure(world: &mut World, operation: SaveOperation, error: SaveError) {
+    error!("{:?} failed: {}", operation, error);
+    world.send_event(SaveLoadFailed { operation, error });
+}
@@ -0,0 +1,27 @@
+use bevy::prelude::*;
+
+use super::error::SaveError;
+
+pub struct Thumbnail {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+impl Thumbnail {
+    pub fn encode_png(&self) -> Result<Vec<u8>, SaveError> {
+        let mut bytes = vec![];
+        let mut encoder = png::Encoder::new(&mut bytes, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_srgb(png::SrgbRenderingIntent::Perceptual);
+        encoder.write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.rgba))
+            .map_err(SaveError::Thumbnail)?;
+        Ok(bytes)
+    }
+}
+
+// Inserted by the renderer, saves are written without a thumbnail when it is missing or returns `None`
+#[derive(Resource)]
+pub struct ThumbnailCapture(pub fn(&World) -> Option<Thumbnail>);
@@ -1,6 +1,8 @@
 use std::sync::Arc;
 
 use bevy::app::{App, Plugin};
+use bevy::ecs::schedule::IntoSystemDescriptor;
+use bevy::window::{CursorMoved, WindowResized, WindowScaleFactorChanged};
 use vulkano::pipeline::graphics::viewport::Viewport;
 
 pub use config::get_vulkano_config;
@@ -8,29 +10,53 @@ use systems::*;
 use systems::create_pipelines;
 
 use super::super::plugins::ViewportResource;
+use camera::{pan_camera, zoom_camera, Camera2d};
+use resources::ViewportPolicy;
+use super::save_load::thumbnail::ThumbnailCapture;
+use thumbnail::capture_thumbnail;
 
 mod systems;
+pub mod camera;
 pub mod resources;
 mod config;
+mod frame;
+pub mod thumbnail;
+mod vertex_pool;
+mod tessellate;
 
-pub struct VulkanPlugin {}
+pub struct VulkanPlugin {
+    pub policy: ViewportPolicy,
+}
 
 impl VulkanPlugin {
     pub fn default() -> Self {
-        Self {}
+        Self {
+            policy: ViewportPolicy::default(),
+        }
     }
 }
 
 impl Plugin for VulkanPlugin {
     fn build(&self, app: &mut App) {
+        // Placeholder until `update_viewport` fits it to the window on the first frame
         let viewport = Viewport {
             origin: [0.0, 0.0],
-            dimensions: [1280.0, 800.0],
+            dimensions: [1.0, 1.0],
             depth_range: 0.0..1.0,
         };
         app
-            .insert_resource(ViewportResource { viewport: Arc::new(viewport) })
+            .insert_resource(ViewportResource { viewport: Arc::new(viewport), policy: self.policy })
+            .add_event::<WindowResized>()
+            .add_event::<WindowScaleFactorChanged>()
+            .add_event::<CursorMoved>()
+            .init_resource::<Camera2d>()
+            .insert_resource(ThumbnailCapture(capture_thumbnail))
             .add_startup_system(create_pipelines)
+            .add_system(update_viewport.before(render))
+            .add_system(zoom_camera.before(render))
+            .add_system(pan_camera.after(update_viewport).before(render))
+            // The polyline feather follows the zoom and the viewport size
+            .add_system(update_vertex_pool.after(update_viewport).after(zoom_camera).after(pan_camera).before(render))
             .add_system(render);
     }
 
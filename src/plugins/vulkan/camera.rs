@@ -0,0 +1,155 @@
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+use bevy::window::CursorMoved;
+use vulkano::pipeline::graphics::viewport::Viewport;
+
+use super::resources::ViewportResource;
+
+const MIN_ZOOM: f32 = 0.05;
+const MAX_ZOOM: f32 = 50.0;
+const ZOOM_PER_LINE: f32 = 1.1;
+// Touchpads report pixels, roughly this many make up one wheel notch
+const PIXELS_PER_LINE: f32 = 100.0;
+const PAN_BUTTON: MouseButton = MouseButton::Right;
+
+// World space is y up. At zoom 1 the view is 2 world units high and as wide as the viewport aspect allows.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct Camera2d {
+    pub position: Vec2,
+    pub zoom: f32,
+}
+
+impl Default for Camera2d {
+    fn default() -> Self {
+        Self {
+            position: Vec2::ZERO,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl Camera2d {
+    // Vulkan clip space has y pointing down, so y is flipped here rather than in the shader
+    pub fn view_projection(&self, dimensions: [f32; 2]) -> Mat4 {
+        let aspect = dimensions[0] / dimensions[1];
+        Mat4::from_scale(Vec3::new(self.zoom / aspect, -self.zoom, 1.0))
+            * Mat4::from_translation(-self.position.extend(0.0))
+    }
+
+    // Screen positions are physical pixels from the top left corner of the window, see `cursor_to_screen`
+    pub fn screen_to_world(&self, screen: Vec2, viewport: &Viewport) -> Vec2 {
+        let origin = Vec2::from(viewport.origin);
+        let dimensions = Vec2::from(viewport.dimensions);
+        let clip = (screen - origin) / dimensions * 2.0 - Vec2::ONE;
+        self.view_projection(viewport.dimensions).inverse().transform_point3(clip.extend(0.0)).truncate()
+    }
+
+    // Takes the cursor position as bevy reports it on the window
+    pub fn cursor_to_world(&self, cursor: Vec2, window: &Window, viewport: &Viewport) -> Vec2 {
+        self.screen_to_world(cursor_to_screen(cursor, window.physical_height() as f32, window.scale_factor() as f32), viewport)
+    }
+
+    pub fn world_to_screen(&self, world: Vec2, viewport: &Viewport) -> Vec2 {
+        let origin = Vec2::from(viewport.origin);
+        let dimensions = Vec2::from(viewport.dimensions);
+        let clip = self.view_projection(viewport.dimensions).transform_point3(world.extend(0.0)).truncate();
+        origin + (clip + Vec2::ONE) / 2.0 * dimensions
+    }
+
+    // World units covered by one physical pixel of the viewport
+    pub fn world_per_pixel(&self, viewport: &Viewport) -> f32 {
+        2.0 / (self.zoom * viewport.dimensions[1])
+    }
+}
+
+pub fn zoom_camera(mut camera: ResMut<Camera2d>, mut wheel: EventReader<MouseWheel>) {
+    let lines: f32 = wheel.iter()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / PIXELS_PER_LINE,
+        })
+        .sum();
+    if lines != 0.0 {
+        camera.zoom = (camera.zoom * ZOOM_PER_LINE.powf(lines)).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+}
+
+// Bevy reports the cursor in logical pixels from the bottom left corner, the viewport is laid out
+// in physical pixels from the top left
+pub fn cursor_to_screen(cursor: Vec2, physical_height: f32, scale_factor: f32) -> Vec2 {
+    Vec2::new(cursor.x * scale_factor, physical_height - cursor.y * scale_factor)
+}
+
+// The world point under the cursor stays under it while dragging
+pub fn pan_camera(
+    mut camera: ResMut<Camera2d>,
+    viewport: Res<ViewportResource>,
+    windows: Res<Windows>,
+    buttons: Res<Input<MouseButton>>,
+    mut cursor_moved: EventReader<CursorMoved>,
+    mut last_cursor: Local<Option<Vec2>>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let cursor = match cursor_moved.iter().rfind(|event| event.id == window.id()) {
+        Some(event) => event.position,
+        None => return,
+    };
+    let previous = last_cursor.replace(cursor);
+    if !buttons.pressed(PAN_BUTTON) {
+        return;
+    }
+    if let Some(previous) = previous {
+        let from = camera.cursor_to_world(previous, window, &viewport.viewport);
+        let to = camera.cursor_to_world(cursor, window, &viewport.viewport);
+        camera.position += from - to;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 1280x800 window at scale factor 2 with a letterboxed viewport
+    fn viewport() -> Viewport {
+        Viewport {
+            origin: [0.0, 160.0],
+            dimensions: [2560.0, 1280.0],
+            depth_range: 0.0..1.0,
+        }
+    }
+
+    #[test]
+    fn cursor_is_flipped_and_scaled() {
+        assert_eq!(cursor_to_screen(Vec2::new(0.0, 0.0), 1600.0, 2.0), Vec2::new(0.0, 1600.0));
+        assert_eq!(cursor_to_screen(Vec2::new(640.0, 800.0), 1600.0, 2.0), Vec2::new(1280.0, 0.0));
+    }
+
+    #[test]
+    fn cursor_maps_back_to_world() {
+        let camera = Camera2d { position: Vec2::new(3.0, -1.0), zoom: 2.5 };
+        let viewport = viewport();
+        for world in [Vec2::new(3.0, -1.0), Vec2::new(3.2, -0.9), Vec2::new(2.5, -1.1)] {
+            let screen = camera.world_to_screen(world, &viewport);
+            let cursor = Vec2::new(screen.x / 2.0, (1600.0 - screen.y) / 2.0);
+            let back = camera.screen_to_world(cursor_to_screen(cursor, 1600.0, 2.0), &viewport);
+            assert!((back - world).length() < 1e-4, "{} != {}", back, world);
+        }
+    }
+
+    // World y is up like bevy's cursor y, so a drag up and right moves the camera down and left
+    #[test]
+    fn drag_keeps_point_under_cursor() {
+        let mut camera = Camera2d::default();
+        let viewport = viewport();
+        let (previous, cursor) = (Vec2::new(600.0, 400.0), Vec2::new(700.0, 450.0));
+        let grabbed = camera.screen_to_world(cursor_to_screen(previous, 1600.0, 2.0), &viewport);
+        let to = camera.screen_to_world(cursor_to_screen(cursor, 1600.0, 2.0), &viewport);
+        camera.position += grabbed - to;
+        let under_cursor = camera.screen_to_world(cursor_to_screen(cursor, 1600.0, 2.0), &viewport);
+        assert!((under_cursor - grabbed).length() < 1e-4);
+        assert!(camera.position.x < 0.0 && camera.position.y < 0.0);
+    }
+}
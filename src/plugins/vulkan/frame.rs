@@ -0,0 +1,251 @@
+use std::any::Any;
+use std::fmt::{Display, Formatter};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use vulkano::swapchain::AcquireError;
+use vulkano::sync::GpuFuture;
+use vulkano_util::renderer::{SwapchainImageView, VulkanoWindowRenderer};
+
+#[derive(Debug, PartialEq)]
+pub enum FrameError {
+    // A minimized window has no surface size to render at
+    Minimized,
+    // The window renderer recreates the swapchain on the next acquire
+    OutOfDate,
+    Acquire(String),
+    Draw(String),
+    // Nothing is acquired anymore after an acquire error
+    Stopped,
+}
+
+impl Display for FrameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FrameError::Minimized => write!(f, "Window is minimized"),
+            FrameError::OutOfDate => write!(f, "Swapchain is out of date"),
+            FrameError::Acquire(error) => write!(f, "Failed to acquire swapchain image: {}", error),
+            FrameError::Draw(error) => write!(f, "Failed to draw: {}", error),
+            FrameError::Stopped => write!(f, "Rendering stopped"),
+        }
+    }
+}
+
+pub fn draw_error(error: impl Display) -> FrameError {
+    FrameError::Draw(error.to_string())
+}
+
+// What a frame is rendered to, the window renderer in the game and a mock in the tests
+pub trait RenderTarget {
+    type Future;
+    type Image;
+
+    fn window_size(&self) -> [f32; 2];
+    fn acquire(&mut self) -> Result<Self::Future, FrameError>;
+    fn image(&self) -> (Self::Image, [u32; 2]);
+    fn present(&mut self, future: Self::Future);
+}
+
+impl RenderTarget for VulkanoWindowRenderer {
+    type Future = Box<dyn GpuFuture>;
+    type Image = SwapchainImageView;
+
+    fn window_size(&self) -> [f32; 2] {
+        VulkanoWindowRenderer::window_size(self)
+    }
+
+    fn acquire(&mut self) -> Result<Self::Future, FrameError> {
+        catch_acquire(|| VulkanoWindowRenderer::acquire(self))
+    }
+
+    fn image(&self) -> (Self::Image, [u32; 2]) {
+        (self.swapchain_image_view(), self.swapchain_image_size())
+    }
+
+    // Present errors never reach the caller, vulkano-util recreates an out of date swapchain and
+    // starts over from a fresh future on any other error
+    fn present(&mut self, future: Self::Future) {
+        VulkanoWindowRenderer::present(self, future, true)
+    }
+}
+
+// vulkano-util only returns `OutOfDate` and panics on every other acquire error. The panic
+// happens before it touches its frame state, so it is turned back into an error here. This needs
+// unwinding, building with `panic = "abort"` is not supported.
+#[cfg(panic = "abort")]
+compile_error!("acquire errors are caught as panics, so the game has to be built with panic = \"unwind\"");
+
+pub fn catch_acquire<F>(acquire: impl FnOnce() -> Result<F, AcquireError>) -> Result<F, FrameError> {
+    match catch_unwind(AssertUnwindSafe(acquire)) {
+        Ok(Ok(future)) => Ok(future),
+        Ok(Err(AcquireError::OutOfDate)) => Err(FrameError::OutOfDate),
+        Ok(Err(error)) => Err(FrameError::Acquire(error.to_string())),
+        Err(panic) => Err(FrameError::Acquire(panic_message(panic))),
+    }
+}
+
+// An acquired image has to be presented, otherwise the window renderer has no future to start
+// the next frame from. A failed draw hands back the future it could not use, and that is presented instead.
+pub fn render_frame<T: RenderTarget>(
+    target: &mut T,
+    draw: impl FnOnce(T::Future, T::Image, [u32; 2]) -> Result<T::Future, (FrameError, T::Future)>,
+) -> Result<(), FrameError> {
+    if target.window_size().contains(&0.0) {
+        return Err(FrameError::Minimized);
+    }
+    let before_future = target.acquire()?;
+    let (image, image_size) = target.image();
+    match draw(before_future, image, image_size) {
+        Ok(after_future) => {
+            target.present(after_future);
+            Ok(())
+        }
+        Err((error, future)) => {
+            target.present(future);
+            Err(error)
+        }
+    }
+}
+
+// The other acquire errors mean a lost surface or device, acquiring again would run into the same
+// panic every frame, so the first one stops rendering for good
+#[derive(Default)]
+pub struct FrameStatus {
+    stopped: bool,
+}
+
+impl FrameStatus {
+    pub fn render<T: RenderTarget>(
+        &mut self,
+        target: &mut T,
+        draw: impl FnOnce(T::Future, T::Image, [u32; 2]) -> Result<T::Future, (FrameError, T::Future)>,
+    ) -> Result<(), FrameError> {
+        if self.stopped {
+            return Err(FrameError::Stopped);
+        }
+        let result = render_frame(target, draw);
+        self.stopped = matches!(result, Err(FrameError::Acquire(_)));
+        result
+    }
+}
+
+fn panic_message(panic: Box<dyn Any + Send>) -> String {
+    match panic.downcast::<String>() {
+        Ok(message) => *message,
+        Err(panic) => panic.downcast_ref::<&str>().map_or("unknown error", |message| message).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Futures are plain numbers, the draw adds one so the test can tell what got presented
+    struct MockTarget {
+        window_size: [f32; 2],
+        acquire: fn() -> Result<u32, AcquireError>,
+        acquired: u32,
+        presented: Vec<u32>,
+    }
+
+    impl MockTarget {
+        fn new(acquire: fn() -> Result<u32, AcquireError>) -> Self {
+            Self {
+                window_size: [1280.0, 800.0],
+                acquire,
+                acquired: 0,
+                presented: vec![],
+            }
+        }
+    }
+
+    impl RenderTarget for MockTarget {
+        type Future = u32;
+        type Image = ();
+
+        fn window_size(&self) -> [f32; 2] {
+            self.window_size
+        }
+
+        fn acquire(&mut self) -> Result<u32, FrameError> {
+            self.acquired += 1;
+            catch_acquire(self.acquire)
+        }
+
+        fn image(&self) -> ((), [u32; 2]) {
+            ((), [self.window_size[0] as u32, self.window_size[1] as u32])
+        }
+
+        fn present(&mut self, future: u32) {
+            self.presented.push(future);
+        }
+    }
+
+    fn draw(future: u32, _: (), _: [u32; 2]) -> Result<u32, (FrameError, u32)> {
+        Ok(future + 1)
+    }
+
+    #[test]
+    fn presents_drawn_frame() {
+        let mut target = MockTarget::new(|| Ok(10));
+        assert_eq!(render_frame(&mut target, draw), Ok(()));
+        assert_eq!(target.presented, vec![11]);
+    }
+
+    #[test]
+    fn minimized_window_is_not_acquired() {
+        let mut target = MockTarget::new(|| Ok(10));
+        target.window_size = [0.0, 0.0];
+        assert_eq!(render_frame(&mut target, draw), Err(FrameError::Minimized));
+        assert_eq!(target.acquired, 0);
+        assert!(target.presented.is_empty());
+    }
+
+    #[test]
+    fn out_of_date_swapchain_skips_frame() {
+        let mut target = MockTarget::new(|| Err(AcquireError::OutOfDate));
+        assert_eq!(render_frame(&mut target, draw), Err(FrameError::OutOfDate));
+        assert!(target.presented.is_empty());
+    }
+
+    #[test]
+    fn acquire_error_skips_frame() {
+        let mut target = MockTarget::new(|| Err(AcquireError::SurfaceLost));
+        assert!(matches!(render_frame(&mut target, draw), Err(FrameError::Acquire(_))));
+        assert!(target.presented.is_empty());
+    }
+
+    // The way vulkano-util reports a lost surface or device
+    #[test]
+    fn acquire_panic_skips_frame() {
+        let mut target = MockTarget::new(|| panic!("Failed to acquire next image: {:?}", AcquireError::DeviceLost));
+        let error = render_frame(&mut target, draw).unwrap_err();
+        assert_eq!(error, FrameError::Acquire(format!("Failed to acquire next image: {:?}", AcquireError::DeviceLost)));
+        assert!(target.presented.is_empty());
+    }
+
+    #[test]
+    fn acquire_error_stops_rendering() {
+        let mut target = MockTarget::new(|| panic!("Failed to acquire next image: {:?}", AcquireError::SurfaceLost));
+        let mut status = FrameStatus::default();
+        assert!(matches!(status.render(&mut target, draw), Err(FrameError::Acquire(_))));
+        assert_eq!(status.render(&mut target, draw), Err(FrameError::Stopped));
+        assert_eq!(target.acquired, 1);
+    }
+
+    #[test]
+    fn out_of_date_keeps_rendering() {
+        let mut target = MockTarget::new(|| Err(AcquireError::OutOfDate));
+        let mut status = FrameStatus::default();
+        assert_eq!(status.render(&mut target, draw), Err(FrameError::OutOfDate));
+        assert_eq!(status.render(&mut target, draw), Err(FrameError::OutOfDate));
+        assert_eq!(target.acquired, 2);
+    }
+
+    #[test]
+    fn failed_draw_still_presents() {
+        let mut target = MockTarget::new(|| Ok(10));
+        let result = render_frame(&mut target, |future, _, _| Err((draw_error("out of memory"), future)));
+        assert_eq!(result, Err(FrameError::Draw("out of memory".to_string())));
+        assert_eq!(target.presented, vec![10]);
+    }
+}
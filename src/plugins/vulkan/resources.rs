@@ -1,43 +1,99 @@
+use std::ops::Range;
 use std::sync::Arc;
 
 use bevy::ecs::system::Resource;
-use vulkano::buffer::{BufferUsage, CpuAccessibleBuffer};
-use vulkano::buffer::TypedBufferAccess;
-use vulkano::command_buffer::{AutoCommandBufferBuilder, CommandBufferUsage, PrimaryAutoCommandBuffer, RenderPassBeginInfo, SubpassContents};
+use bevy::log::warn;
+use bevy::math::Mat4;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer::{AutoCommandBufferBuilder, CommandBufferUsage, PipelineExecutionError, PrimaryAutoCommandBuffer, RenderPassBeginInfo, SubpassContents};
 use vulkano::command_buffer::allocator::StandardCommandBufferAllocator;
 use vulkano::descriptor_set::allocator::StandardDescriptorSetAllocator;
 use vulkano::device::{self, Device, DeviceOwned};
 use vulkano::format::Format;
-use vulkano::image::{ImageLayout, ImageUsage, StorageImage};
+use vulkano::image::{ImageError, ImageLayout, ImageUsage, StorageImage};
 use vulkano::memory::allocator::StandardMemoryAllocator;
-use vulkano::pipeline::graphics::input_assembly;
-use vulkano::pipeline::graphics::input_assembly::InputAssemblyState;
+use vulkano::pipeline::graphics::color_blend::ColorBlendState;
+use vulkano::pipeline::graphics::input_assembly::{InputAssemblyState, PrimitiveTopology};
 use vulkano::pipeline::graphics::vertex_input::{VertexInputAttributeDescription, VertexInputBindingDescription, VertexInputRate, VertexInputState};
 use vulkano::pipeline::graphics::viewport::{Viewport, ViewportState};
-use vulkano::pipeline::GraphicsPipeline;
+use vulkano::pipeline::{GraphicsPipeline, Pipeline};
 use vulkano::render_pass::{AttachmentDescription, AttachmentReference, Framebuffer, FramebufferCreateInfo, LoadOp, RenderPass, RenderPassCreateInfo, StoreOp, Subpass, SubpassDescription};
-use vulkano::swapchain::AcquireError;
-use vulkano::sync::GpuFuture;
+use vulkano::sync::{self, GpuFuture};
 use vulkano_util::renderer::{DeviceImageView, SwapchainImageView, VulkanoWindowRenderer};
 
+use super::frame::{draw_error, FrameError};
+use super::vertex_pool::{DrawMode, Vertex, VertexBuffers, VertexPool};
+
 #[derive(Resource)]
 pub struct VulkanPipeline {
     queue: Arc<device::Queue>,
     command_buffer_allocator: StandardCommandBufferAllocator,
     descriptor_set_allocator: StandardDescriptorSetAllocator,
-    graphics_pipeline: Arc<GraphicsPipeline>,
+    pipelines: BodyPipelines,
     render_pass: Arc<RenderPass>,
     allocator: Arc<StandardMemoryAllocator>,
     image: DeviceImageView,
+    image_size: [u32; 2],
+    pub vertices: VertexPool,
+    vertex_buffers: VertexBuffers,
+    // Anti-aliasing fringe the polylines in `vertices` were tessellated with
+    pub feather: f32,
 }
 
 impl VulkanPipeline {
     pub fn new(allocator: Arc<StandardMemoryAllocator>,
-               window_renderer: &VulkanoWindowRenderer,
-               viewport: Viewport) -> Self {
+               window_renderer: &VulkanoWindowRenderer) -> Self {
         let queue = window_renderer.graphics_queue().clone();
         let device = queue.device().clone();
-        let render_pass = Self::create_render_pass(device.clone());
+        let render_pass = Self::create_render_pass(device.clone(), ImageLayout::General, ImageLayout::PresentSrc);
+        let pipelines = BodyPipelines::new(device, render_pass.clone());
+
+        let image_size = window_renderer.swapchain_image_size();
+        let image = Self::create_image(&allocator, queue.clone(), image_size).unwrap();
+
+        Self {
+            queue,
+            command_buffer_allocator: StandardCommandBufferAllocator::new(
+                allocator.device().clone(),
+                Default::default(),
+            ),
+            descriptor_set_allocator: StandardDescriptorSetAllocator::new(
+                allocator.device().clone(),
+            ),
+            pipelines,
+            render_pass,
+            vertices: VertexPool::default(),
+            vertex_buffers: VertexBuffers::new(allocator.clone()),
+            allocator,
+            image,
+            image_size,
+            feather: 0.0,
+        }
+    }
+    // The swapchain is recreated by the window renderer, only the images sized after it live here
+    pub fn resize(&mut self, image_size: [u32; 2]) -> Result<(), FrameError> {
+        if self.image_size != image_size {
+            self.image = Self::create_image(&self.allocator, self.queue.clone(), image_size).map_err(draw_error)?;
+            self.image_size = image_size;
+        }
+        Ok(())
+    }
+    fn create_image(allocator: &StandardMemoryAllocator, queue: Arc<device::Queue>, image_size: [u32; 2]) -> Result<DeviceImageView, ImageError> {
+        StorageImage::general_purpose_image_view(
+            allocator,
+            queue,
+            image_size,
+            Format::R32G32B32A32_SFLOAT,
+            ImageUsage {
+                sampled: true,
+                storage: true,
+                transfer_dst: true,
+                ..ImageUsage::empty()
+            },
+        )
+    }
+    // The viewport is dynamic and set per draw, so window resizes do not need a new pipeline
+    fn create_graphics_pipeline(device: Arc<Device>, render_pass: Arc<RenderPass>, topology: PrimitiveTopology) -> Arc<GraphicsPipeline> {
         let vertex_input_state = VertexInputState::default()
             .binding(0, VertexInputBindingDescription {
                 stride: 32,
@@ -53,60 +109,31 @@ impl VulkanPipeline {
                 format: Format::R32G32B32A32_SFLOAT,
                 offset: 16,
             });
-        let graphics_pipeline = {
-            let vertex_shader = vs::load(device.clone()).unwrap();
-            let fragment_shader = fs::load(device.clone()).unwrap();
-
-            let input_assembly_state = InputAssemblyState::new()
-                .topology(input_assembly::PrimitiveTopology::PointList);
-
-            GraphicsPipeline::start()
-                .vertex_shader(vertex_shader.entry_point("main").unwrap(), ())
-                .vertex_input_state(vertex_input_state)
-                .input_assembly_state(input_assembly_state)
-                .viewport_state(ViewportState::viewport_fixed_scissor_irrelevant([viewport]))
-                .fragment_shader(fragment_shader.entry_point("main").unwrap(), ())
-                .render_pass(Subpass::from(render_pass.clone(), 0).unwrap())
-                .build(device.clone())
-                .unwrap()
-        };
+        let vertex_shader = vs::load(device.clone()).unwrap();
+        let fragment_shader = fs::load(device.clone()).unwrap();
 
-        let image = StorageImage::general_purpose_image_view(
-            &allocator,
-            queue.clone(),
-            window_renderer.swapchain_image_size(),
-            Format::R32G32B32A32_SFLOAT,
-            ImageUsage {
-                sampled: true,
-                storage: true,
-                transfer_dst: true,
-                ..ImageUsage::empty()
-            },
-        )
-            .unwrap();
+        let input_assembly_state = InputAssemblyState::new()
+            .topology(topology);
 
-        Self {
-            queue,
-            command_buffer_allocator: StandardCommandBufferAllocator::new(
-                allocator.device().clone(),
-                Default::default(),
-            ),
-            descriptor_set_allocator: StandardDescriptorSetAllocator::new(
-                allocator.device().clone(),
-            ),
-            graphics_pipeline,
-            render_pass,
-            allocator,
-            image,
-        }
+        GraphicsPipeline::start()
+            .vertex_shader(vertex_shader.entry_point("main").unwrap(), ())
+            .vertex_input_state(vertex_input_state)
+            .input_assembly_state(input_assembly_state)
+            .viewport_state(ViewportState::viewport_dynamic_scissor_irrelevant())
+            .fragment_shader(fragment_shader.entry_point("main").unwrap(), ())
+            // Polyline fringes fade out through their alpha
+            .color_blend_state(ColorBlendState::new(1).blend_alpha())
+            .render_pass(Subpass::from(render_pass, 0).unwrap())
+            .build(device)
+            .unwrap()
     }
-    fn create_render_pass(device: Arc<Device>) -> Arc<RenderPass> {
+    pub(super) fn create_render_pass(device: Arc<Device>, initial_layout: ImageLayout, final_layout: ImageLayout) -> Arc<RenderPass> {
         let attachment_description = AttachmentDescription {
             format: Some(Format::B8G8R8A8_SRGB),
             load_op: LoadOp::Clear,
             store_op: StoreOp::Store,
-            initial_layout: ImageLayout::General,
-            final_layout: ImageLayout::PresentSrc,
+            initial_layout,
+            final_layout,
             ..Default::default()
         };
 
@@ -132,62 +159,37 @@ impl VulkanPipeline {
     }
     pub fn draw(
         &mut self,
-        before_future: Result<Box<dyn GpuFuture>, AcquireError>,
+        before_future: Box<dyn GpuFuture>,
         target: SwapchainImageView,
         viewport: Viewport,
-        pixels: Vec<[[f32; 4]; 2]>,
-    ) -> Box<dyn GpuFuture> {
+        view_projection: Mat4,
+    ) -> Result<Box<dyn GpuFuture>, (FrameError, Box<dyn GpuFuture>)> {
+        // Nothing is submitted yet, the acquired image is handed back untouched
+        let command_buffer = match self.create_command_buffer(target, viewport, view_projection) {
+            Ok(command_buffer) => command_buffer,
+            Err(error) => return Err((error, before_future)),
+        };
+
+        let device = self.queue.device().clone();
+        before_future
+            .then_execute(self.queue.clone(), command_buffer)
+            .map(|after_future| after_future.boxed())
+            // The acquire future is used up by the failed submit, the frame starts over from now
+            .map_err(|error| (draw_error(error), sync::now(device).boxed()))
+    }
+    fn create_command_buffer(&mut self, target: SwapchainImageView, viewport: Viewport, view_projection: Mat4) -> Result<PrimaryAutoCommandBuffer, FrameError> {
         let frame_buffer = Framebuffer::new(self.render_pass.clone(), FramebufferCreateInfo {
             attachments: vec![target],
             ..Default::default()
         })
-            .unwrap();
+            .map_err(draw_error)?;
 
-        let mut command_buffer_builder = AutoCommandBufferBuilder::primary(
-            &self.command_buffer_allocator,
-            self.queue.queue_family_index(),
-            CommandBufferUsage::OneTimeSubmit,
-        )
-            .unwrap();
-
-        command_buffer_builder
-            .begin_render_pass(
-                RenderPassBeginInfo {
-                    clear_values: vec![Some([0.0; 4].into())],
-                    ..RenderPassBeginInfo::framebuffer(frame_buffer.clone())
-                },
-                SubpassContents::SecondaryCommandBuffers,
-            )
-            .unwrap();
-
-        let command_buffer = self.create_command_buffer(frame_buffer, viewport, pixels);
-
-        let after_future = before_future
-            .unwrap()
-            .then_execute(self.queue.clone(), command_buffer)
-            .unwrap();
-
-        after_future.boxed()
-    }
-    fn create_command_buffer(&self, frame_buffer: Arc<Framebuffer>, viewport: Viewport, pixels: Vec<[[f32; 4]; 2]>) -> PrimaryAutoCommandBuffer {
-        let command_buffer_allocator =
-            StandardCommandBufferAllocator::new(self.queue.device().clone(), Default::default());
         let mut builder = AutoCommandBufferBuilder::primary(
-            &command_buffer_allocator,
+            &self.command_buffer_allocator,
             self.queue.queue_family_index(),
             CommandBufferUsage::OneTimeSubmit,
         )
-            .unwrap();
-
-        let vertex_buffer = CpuAccessibleBuffer::from_iter(
-            &self.allocator,
-            BufferUsage {
-                vertex_buffer: true,
-                ..Default::default()
-            },
-            false,
-            pixels,
-        ).expect("Failed to create vertex buffer.");
+            .map_err(draw_error)?;
 
         builder
             // Before we can draw, we have to *enter a render pass*.
@@ -200,22 +202,124 @@ impl VulkanPipeline {
                 },
                 SubpassContents::Inline,
             )
-            .unwrap()
-            .set_viewport(0, [viewport.clone()])
-            .bind_pipeline_graphics(self.graphics_pipeline.clone())
-            .bind_vertex_buffers(0, vertex_buffer.clone())
-            .draw(vertex_buffer.len() as u32, 1, 0, 0)
-            .unwrap()
+            .map_err(draw_error)?
+            .set_viewport(0, [viewport.clone()]);
+
+        if let Some(vertex_buffer) = self.vertex_buffers.next_buffer(&mut self.vertices) {
+            self.pipelines.draw(&mut builder, vertex_buffer, self.vertices.draws(), view_projection).map_err(draw_error)?;
+        }
+
+        builder
             .end_render_pass()
-            .unwrap();
+            .map_err(draw_error)?;
 
-        builder.build().unwrap()
+        builder.build().map_err(draw_error)
+    }
+}
+
+// One pipeline per topology a body can be drawn with, they share shaders and vertex layout
+pub(super) struct BodyPipelines {
+    points: Arc<GraphicsPipeline>,
+    line_strip: Arc<GraphicsPipeline>,
+    triangles: Arc<GraphicsPipeline>,
+}
+
+impl BodyPipelines {
+    pub(super) fn new(device: Arc<Device>, render_pass: Arc<RenderPass>) -> Self {
+        Self {
+            points: VulkanPipeline::create_graphics_pipeline(device.clone(), render_pass.clone(), PrimitiveTopology::PointList),
+            line_strip: VulkanPipeline::create_graphics_pipeline(device.clone(), render_pass.clone(), PrimitiveTopology::LineStrip),
+            triangles: VulkanPipeline::create_graphics_pipeline(device, render_pass, PrimitiveTopology::TriangleList),
+        }
+    }
+
+    fn get(&self, mode: DrawMode) -> &Arc<GraphicsPipeline> {
+        match mode {
+            DrawMode::Points => &self.points,
+            DrawMode::LineStrip => &self.line_strip,
+            DrawMode::Triangles => &self.triangles,
+        }
+    }
+
+    // The draws are expected grouped by mode, the pipeline is only rebound when the mode changes
+    pub(super) fn draw(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        vertex_buffer: Arc<CpuAccessibleBuffer<[Vertex]>>,
+        draws: &[(DrawMode, Range<usize>)],
+        view_projection: Mat4,
+    ) -> Result<(), PipelineExecutionError> {
+        builder.bind_vertex_buffers(0, vertex_buffer);
+        let mut bound = None;
+        for (mode, range) in draws {
+            if bound != Some(*mode) {
+                let pipeline = self.get(*mode);
+                builder
+                    .bind_pipeline_graphics(pipeline.clone())
+                    .push_constants(pipeline.layout().clone(), 0, push_constants(view_projection));
+                bound = Some(*mode);
+            }
+            builder.draw(range.len() as u32, 1, range.start as u32, 0)?;
+        }
+        Ok(())
+    }
+}
+
+// How the rendered area is fitted into the window. Sizes are in physical pixels, so a
+// scale factor change is handled like any other resize.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ViewportPolicy {
+    #[default]
+    Stretch,
+    // Largest centered area with the given width / height ratio
+    Letterbox { aspect: f32 },
+    // Largest whole multiple of the base resolution that fits, centered
+    IntegerScale { width: u32, height: u32 },
+}
+
+impl ViewportPolicy {
+    // A zero, negative or NaN size would give vulkano a viewport it panics on
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            ViewportPolicy::Stretch => true,
+            ViewportPolicy::Letterbox { aspect } => aspect.is_finite() && aspect > 0.0,
+            ViewportPolicy::IntegerScale { width, height } => width > 0 && height > 0,
+        }
+    }
+
+    pub fn fit(&self, window_size: [f32; 2]) -> Viewport {
+        let [window_width, window_height] = window_size;
+        let dimensions = match *self {
+            _ if !self.is_valid() => {
+                warn!("Invalid viewport policy {:?}, stretching to the window instead", self);
+                window_size
+            }
+            ViewportPolicy::Stretch => window_size,
+            ViewportPolicy::Letterbox { aspect } => {
+                if window_width / window_height > aspect {
+                    [window_height * aspect, window_height]
+                } else {
+                    [window_width, window_width / aspect]
+                }
+            }
+            ViewportPolicy::IntegerScale { width, height } => {
+                // A window smaller than the base resolution still shows it unscaled, clipped at the edges
+                let scale = (window_width / width as f32).min(window_height / height as f32).floor().max(1.0);
+                [width as f32 * scale, height as f32 * scale]
+            }
+        };
+        Viewport {
+            origin: [((window_width - dimensions[0]) / 2.0).floor(), ((window_height - dimensions[1]) / 2.0).floor()],
+            dimensions,
+            depth_range: 0.0..1.0,
+        }
     }
 }
 
 #[derive(Resource)]
 pub struct ViewportResource {
     pub viewport: Arc<Viewport>,
+    pub policy: ViewportPolicy,
 }
 
 impl ViewportResource {
@@ -228,7 +332,13 @@ impl ViewportResource {
     }
 }
 
-mod vs {
+pub(super) fn push_constants(view_projection: Mat4) -> vs::ty::PushConstants {
+    vs::ty::PushConstants {
+        view_projection: view_projection.to_cols_array_2d(),
+    }
+}
+
+pub(super) mod vs {
     vulkano_shaders::shader! {
         ty: "vertex",
         path: "./src/shaders/shader.vert"
@@ -240,4 +350,28 @@ mod fs {
         ty: "fragment",
         path: "./src/shaders/shader.frag"
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_policies_stretch() {
+        for policy in [
+            ViewportPolicy::Letterbox { aspect: 0.0 },
+            ViewportPolicy::Letterbox { aspect: -1.5 },
+            ViewportPolicy::Letterbox { aspect: f32::NAN },
+            ViewportPolicy::IntegerScale { width: 0, height: 240 },
+            ViewportPolicy::IntegerScale { width: 320, height: 0 },
+        ] {
+            let viewport = policy.fit([1280.0, 800.0]);
+            assert_eq!((viewport.origin, viewport.dimensions), ([0.0, 0.0], [1280.0, 800.0]), "{:?}", policy);
+        }
+    }
+
+    #[test]
+    fn letterbox_centers_area() {
+        let viewport = ViewportPolicy::Letterbox { aspect: 2.0 }.fit([1280.0, 800.0]);
+        assert_eq!((viewport.origin, viewport.dimensions), ([0.0, 80.0], [1280.0, 640.0]));
+    }
+}
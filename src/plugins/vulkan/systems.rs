@@ -1,34 +1,108 @@
-use bevy_ecs::system::{Commands, NonSend, NonSendMut, Query, ResMut};
+use bevy_ecs::change_detection::DetectChanges;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::query::{Changed, Or};
+use bevy_ecs::event::EventReader;
+use bevy_ecs::system::{Commands, Local, NonSend, NonSendMut, Query, RemovedComponents, Res, ResMut};
+use bevy::log::{error, warn};
+use bevy::window::{WindowResized, WindowScaleFactorChanged};
 use bevy_vulkano::{BevyVulkanoContext, BevyVulkanoWindows};
+use std::sync::Arc;
 
-use crate::plugins::components::Body;
+use crate::plugins::components::{Body, BodyRenderStyle};
 use crate::plugins::ViewportResource;
 
+use super::camera::Camera2d;
+use super::frame::{FrameError, FrameStatus};
 use super::resources::VulkanPipeline;
+use super::tessellate::body_vertices;
+use super::thumbnail::ThumbnailRenderer;
 
-pub fn create_pipelines(mut commands: Commands, context: NonSend<BevyVulkanoContext>, vulkano_windows: NonSend<BevyVulkanoWindows>, viewport: NonSend<ViewportResource>) {
+pub fn create_pipelines(mut commands: Commands, context: NonSend<BevyVulkanoContext>, vulkano_windows: NonSend<BevyVulkanoWindows>) {
     let primary_window = vulkano_windows.get_primary_window_renderer().unwrap();
     // Create your render pass & pipelines (MyRenderPass could contain your pipelines, e.g. draw_circle)
-    let my_pipeline = VulkanPipeline::new(context.context.memory_allocator().clone(), primary_window, viewport.get_viewport());
+    let my_pipeline = VulkanPipeline::new(context.context.memory_allocator().clone(), primary_window);
+    let thumbnail_renderer = ThumbnailRenderer::new(context.context.memory_allocator().clone(), primary_window.graphics_queue().clone());
     // Insert as a resource
     commands.insert_resource(my_pipeline);
+    commands.insert_resource(thumbnail_renderer);
+}
+
+// Refits the viewport when the window size or scale factor changes, or the policy was replaced
+pub fn update_viewport(
+    vulkano_windows: NonSend<BevyVulkanoWindows>,
+    mut viewport: ResMut<ViewportResource>,
+    mut resized: EventReader<WindowResized>,
+    mut scale_factor_changed: EventReader<WindowScaleFactorChanged>,
+) {
+    let window_changed = resized.iter().count() + scale_factor_changed.iter().count() > 0;
+    if !window_changed && !viewport.is_changed() {
+        return;
+    }
+    let window_size = match vulkano_windows.get_primary_window_renderer() {
+        Some(primary_window) => primary_window.window_size(),
+        None => return,
+    };
+    if window_size.contains(&0.0) {
+        return;
+    }
+
+    let fitted = viewport.policy.fit(window_size);
+    // Only written when it differs, otherwise the write itself would count as a change next frame
+    if fitted.origin != viewport.viewport.origin || fitted.dimensions != viewport.viewport.dimensions {
+        viewport.viewport = Arc::new(fitted);
+    }
+}
+
+type StyledBody<'a> = (Entity, &'a Body, Option<&'a BodyRenderStyle>);
+type Restyled = Or<(Changed<Body>, Changed<BodyRenderStyle>)>;
+
+// Only bodies that changed since the last frame are copied into the vertex pool. This runs apart
+// from `render` so skipped frames still take the changes, they would not be reported again.
+pub fn update_vertex_pool(
+    mut pipeline: ResMut<VulkanPipeline>,
+    viewport: Res<ViewportResource>,
+    camera: Res<Camera2d>,
+    changed_bodies: Query<StyledBody, Restyled>,
+    bodies: Query<StyledBody>,
+    removed_bodies: RemovedComponents<Body>,
+    removed_styles: RemovedComponents<BodyRenderStyle>,
+) {
+    for entity in removed_bodies.iter() {
+        pipeline.vertices.remove(entity);
+    }
+    // The polyline fringe is one pixel wide, after a zoom or resize the polylines are tessellated again
+    let feather = camera.world_per_pixel(&viewport.viewport);
+    let feather_changed = feather != pipeline.feather;
+    pipeline.feather = feather;
+    let restyled = removed_styles.iter().filter_map(|entity| bodies.get(entity).ok());
+    let rescaled = feather_changed
+        .then(|| bodies.iter().filter(|(_, _, style)| matches!(style, Some(BodyRenderStyle::Polyline { .. }))))
+        .into_iter()
+        .flatten();
+    for (entity, body, style) in restyled.chain(rescaled).chain(changed_bodies.iter()) {
+        let (mode, vertices) = body_vertices(body, style.copied().unwrap_or_default(), feather);
+        pipeline.vertices.set(entity, mode, vertices);
+    }
 }
 
 pub fn render(
     mut vulkano_windows: NonSendMut<BevyVulkanoWindows>,
     mut pipeline: ResMut<VulkanPipeline>,
-    viewport: ResMut<ViewportResource>,
-    query: Query<&Body>,
+    viewport: Res<ViewportResource>,
+    camera: Res<Camera2d>,
+    mut status: Local<FrameStatus>,
 ) {
     let primary_window = vulkano_windows.get_primary_window_renderer_mut().unwrap();
-    let previous_frame_end = primary_window.acquire();
-    let target = primary_window.swapchain_image_view();
-    let mut pixels:Vec<[[f32; 4]; 2]> = vec![];
-    for body in query.iter(){
-        pixels.extend(body.get_pixels())
+    let view_projection = camera.view_projection(viewport.viewport.dimensions);
+    let result = status.render(primary_window, |before_future, target, image_size| {
+        if let Err(error) = pipeline.resize(image_size) {
+            return Err((error, before_future));
+        }
+        pipeline.draw(before_future, target, viewport.get_viewport(), view_projection)
+    });
+    match result {
+        Ok(()) | Err(FrameError::Minimized) | Err(FrameError::OutOfDate) | Err(FrameError::Stopped) => {}
+        Err(error @ FrameError::Acquire(_)) => error!("Stopped rendering: {}", error),
+        Err(error) => warn!("Skipping frame: {}", error),
     }
-
-    let future = pipeline.draw(previous_frame_end, target, viewport.get_viewport(),pixels);
-
-    primary_window.present(future, true);
 }
@@ -0,0 +1,95 @@
+use std::f32::consts::TAU;
+
+use bevy::math::Vec2;
+
+use crate::plugins::components::{Body, BodyRenderStyle, Vertebrae};
+
+use super::vertex_pool::{DrawMode, Vertex};
+
+const JOIN_SEGMENTS: usize = 16;
+
+// `feather` is the width of the anti-aliasing fringe in world units, about one pixel
+pub fn body_vertices(body: &Body, style: BodyRenderStyle, feather: f32) -> (DrawMode, Vec<Vertex>) {
+    match style {
+        BodyRenderStyle::Points => (DrawMode::Points, body.get_pixels()),
+        BodyRenderStyle::LineStrip => (DrawMode::LineStrip, body.get_pixels()),
+        BodyRenderStyle::Polyline { width } => (DrawMode::Triangles, polyline(&body.spine, width.max(0.0) / 2.0, feather)),
+    }
+}
+
+// Every segment is a quad and every vertebra a disc, so joins and end caps come out round. The
+// fringe fades to transparent around both and is blended over whatever lies below.
+fn polyline(spine: &[Vertebrae], radius: f32, feather: f32) -> Vec<Vertex> {
+    let mut vertices = vec![];
+    for pair in spine.windows(2) {
+        segment(&mut vertices, &pair[0], &pair[1], radius, feather);
+    }
+    for vertebrae in spine {
+        join(&mut vertices, vertebrae, radius, feather);
+    }
+    vertices
+}
+
+fn segment(vertices: &mut Vec<Vertex>, start: &Vertebrae, end: &Vertebrae, radius: f32, feather: f32) {
+    let from = position(start);
+    let to = position(end);
+    let normal = match (to - from).try_normalize() {
+        Some(direction) => direction.perp(),
+        // Overlapping vertebrae are covered by their joins
+        None => return,
+    };
+
+    let inner = normal * radius;
+    let outer = normal * (radius + feather);
+    quad(vertices, [
+        vertex(from - inner, start, 1.0),
+        vertex(from + inner, start, 1.0),
+        vertex(to + inner, end, 1.0),
+        vertex(to - inner, end, 1.0),
+    ]);
+    for side in [1.0, -1.0] {
+        quad(vertices, [
+            vertex(from + inner * side, start, 1.0),
+            vertex(from + outer * side, start, 0.0),
+            vertex(to + outer * side, end, 0.0),
+            vertex(to + inner * side, end, 1.0),
+        ]);
+    }
+}
+
+fn join(vertices: &mut Vec<Vertex>, vertebrae: &Vertebrae, radius: f32, feather: f32) {
+    let center = position(vertebrae);
+    let ring = |i: usize| {
+        let angle = i as f32 / JOIN_SEGMENTS as f32 * TAU;
+        Vec2::new(angle.cos(), angle.sin())
+    };
+    for i in 0..JOIN_SEGMENTS {
+        let (a, b) = (ring(i), ring(i + 1));
+        vertices.extend([
+            vertex(center, vertebrae, 1.0),
+            vertex(center + a * radius, vertebrae, 1.0),
+            vertex(center + b * radius, vertebrae, 1.0),
+        ]);
+        quad(vertices, [
+            vertex(center + a * radius, vertebrae, 1.0),
+            vertex(center + a * (radius + feather), vertebrae, 0.0),
+            vertex(center + b * (radius + feather), vertebrae, 0.0),
+            vertex(center + b * radius, vertebrae, 1.0),
+        ]);
+    }
+}
+
+fn quad(vertices: &mut Vec<Vertex>, [a, b, c, d]: [Vertex; 4]) {
+    vertices.extend([a, b, c, a, c, d]);
+}
+
+fn position(vertebrae: &Vertebrae) -> Vec2 {
+    Vec2::new(vertebrae.position[0], vertebrae.position[1])
+}
+
+// Keeps z and w of the vertebra, only x and y are moved off the spine
+fn vertex(point: Vec2, vertebrae: &Vertebrae, alpha: f32) -> Vertex {
+    let [_, _, z, w] = vertebrae.position;
+    let [r, g, b, a] = vertebrae.color;
+    [[point.x, point.y, z, w], [r, g, b, a * alpha]]
+}
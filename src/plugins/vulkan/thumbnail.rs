@@ -0,0 +1,224 @@
+use std::sync::Arc;
+
+use bevy::ecs::system::Resource;
+use bevy::math::Mat4;
+use bevy::ecs::world::World;
+use vulkano::buffer::{BufferUsage, CpuAccessibleBuffer};
+use vulkano::command_buffer::{AutoCommandBufferBuilder, CommandBufferUsage, CopyImageToBufferInfo, RenderPassBeginInfo, SubpassContents};
+use vulkano::command_buffer::allocator::StandardCommandBufferAllocator;
+use vulkano::device::{Queue, DeviceOwned};
+use vulkano::format::Format;
+use vulkano::image::{AttachmentImage, ImageLayout, ImageUsage};
+use vulkano::image::view::ImageView;
+use vulkano::memory::allocator::StandardMemoryAllocator;
+use vulkano::pipeline::graphics::viewport::Viewport;
+use vulkano::render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass};
+use vulkano::sync::{self, GpuFuture};
+
+use crate::plugins::components::{Body, BodyRenderStyle};
+use crate::plugins::save_load::thumbnail::Thumbnail;
+
+use super::camera::Camera2d;
+use super::resources::{BodyPipelines, VulkanPipeline};
+use super::tessellate::body_vertices;
+use super::vertex_pool::{DrawMode, Vertex};
+
+pub const THUMBNAIL_SIZE: [u32; 2] = [160, 100];
+
+// Draws the world into its own small image instead of reading back the swapchain, so it
+// needs no window and works on a headless device like lavapipe.
+#[derive(Resource)]
+pub struct ThumbnailRenderer {
+    queue: Arc<Queue>,
+    allocator: Arc<StandardMemoryAllocator>,
+    command_buffer_allocator: StandardCommandBufferAllocator,
+    pipelines: BodyPipelines,
+    render_pass: Arc<RenderPass>,
+}
+
+impl ThumbnailRenderer {
+    pub fn new(allocator: Arc<StandardMemoryAllocator>, queue: Arc<Queue>) -> Self {
+        let device = queue.device().clone();
+        let render_pass = VulkanPipeline::create_render_pass(device.clone(), ImageLayout::Undefined, ImageLayout::TransferSrcOptimal);
+        let pipelines = BodyPipelines::new(device.clone(), render_pass.clone());
+
+        Self {
+            queue,
+            allocator,
+            command_buffer_allocator: StandardCommandBufferAllocator::new(device, Default::default()),
+            pipelines,
+            render_pass,
+        }
+    }
+
+    pub(super) fn render(&self, mut bodies: Vec<(DrawMode, Vec<Vertex>)>, view_projection: Mat4) -> Option<Thumbnail> {
+        let image = AttachmentImage::with_usage(
+            &self.allocator,
+            THUMBNAIL_SIZE,
+            Format::B8G8R8A8_SRGB,
+            ImageUsage {
+                color_attachment: true,
+                transfer_src: true,
+                ..ImageUsage::empty()
+            },
+        ).ok()?;
+        let frame_buffer = Framebuffer::new(self.render_pass.clone(), FramebufferCreateInfo {
+            attachments: vec![ImageView::new_default(image.clone()).ok()?],
+            ..Default::default()
+        }).ok()?;
+
+        let pixel_count = (THUMBNAIL_SIZE[0] * THUMBNAIL_SIZE[1] * 4) as usize;
+        let target = CpuAccessibleBuffer::from_iter(
+            &self.allocator,
+            BufferUsage {
+                transfer_dst: true,
+                ..Default::default()
+            },
+            false,
+            (0..pixel_count).map(|_| 0u8),
+        ).ok()?;
+
+        let mut builder = AutoCommandBufferBuilder::primary(
+            &self.command_buffer_allocator,
+            self.queue.queue_family_index(),
+            CommandBufferUsage::OneTimeSubmit,
+        ).ok()?;
+
+        builder
+            .begin_render_pass(
+                RenderPassBeginInfo {
+                    clear_values: vec![Some([0.0, 0.0, 1.0, 1.0].into())],
+                    ..RenderPassBeginInfo::framebuffer(frame_buffer)
+                },
+                SubpassContents::Inline,
+            )
+            .ok()?
+            .set_viewport(0, [thumbnail_viewport()]);
+
+        bodies.sort_by_key(|(mode, _)| *mode);
+        let mut draws = vec![];
+        let mut vertices = vec![];
+        for (mode, body) in bodies {
+            draws.push((mode, vertices.len()..vertices.len() + body.len()));
+            vertices.extend(body);
+        }
+        // A vertex buffer cannot be empty, an empty world still gets the cleared background
+        if !vertices.is_empty() {
+            let vertex_buffer = CpuAccessibleBuffer::from_iter(
+                &self.allocator,
+                BufferUsage {
+                    vertex_buffer: true,
+                    ..Default::default()
+                },
+                false,
+                vertices,
+            ).ok()?;
+            self.pipelines.draw(&mut builder, vertex_buffer, &draws, view_projection).ok()?;
+        }
+        builder
+            .end_render_pass()
+            .ok()?
+            .copy_image_to_buffer(CopyImageToBufferInfo::image_buffer(image, target.clone()))
+            .ok()?;
+
+        sync::now(self.queue.device().clone())
+            .then_execute(self.queue.clone(), builder.build().ok()?)
+            .ok()?
+            .then_signal_fence_and_flush()
+            .ok()?
+            .wait(None)
+            .ok()?;
+
+        let bgra = target.read().ok()?;
+        Some(Thumbnail {
+            width: THUMBNAIL_SIZE[0],
+            height: THUMBNAIL_SIZE[1],
+            rgba: bgra.chunks(4).flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]]).collect(),
+        })
+    }
+}
+
+pub fn capture_thumbnail(world: &World) -> Option<Thumbnail> {
+    let renderer = world.get_resource::<ThumbnailRenderer>()?;
+    // Same camera as the window, only the aspect follows the thumbnail
+    let camera = world.get_resource::<Camera2d>().copied().unwrap_or_default();
+    let viewport = thumbnail_viewport();
+    let feather = camera.world_per_pixel(&viewport);
+    let bodies = world.iter_entities()
+        .filter_map(|entity| {
+            let body = world.get::<Body>(entity)?;
+            let style = world.get::<BodyRenderStyle>(entity).copied().unwrap_or_default();
+            Some(body_vertices(body, style, feather))
+        })
+        .collect();
+    renderer.render(bodies, camera.view_projection(viewport.dimensions))
+}
+
+fn thumbnail_viewport() -> Viewport {
+    Viewport {
+        origin: [0.0, 0.0],
+        dimensions: [THUMBNAIL_SIZE[0] as f32, THUMBNAIL_SIZE[1] as f32],
+        depth_range: 0.0..1.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vulkano::device::{Device, DeviceCreateInfo, QueueCreateInfo};
+    use vulkano::instance::{Instance, InstanceCreateInfo};
+    use vulkano::VulkanLibrary;
+
+    use crate::plugins::components::Vertebrae;
+
+    use super::*;
+
+    // Any device will do, lavapipe included. None when the machine has no Vulkan at all.
+    fn headless_queue() -> Option<(Arc<StandardMemoryAllocator>, Arc<Queue>)> {
+        let library = VulkanLibrary::new().ok()?;
+        let instance = Instance::new(library, InstanceCreateInfo {
+            enumerate_portability: true,
+            ..Default::default()
+        }).ok()?;
+        let (physical_device, queue_family_index) = instance.enumerate_physical_devices().ok()?
+            .find_map(|device| {
+                let index = device.queue_family_properties().iter().position(|family| family.queue_flags.graphics)?;
+                Some((device, index as u32))
+            })?;
+        let (device, mut queues) = Device::new(physical_device, DeviceCreateInfo {
+            queue_create_infos: vec![QueueCreateInfo {
+                queue_family_index,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }).ok()?;
+        Some((Arc::new(StandardMemoryAllocator::new_default(device)), queues.next()?))
+    }
+
+    // Skipped without a Vulkan device, unless REQUIRE_VULKAN is set so a CI job with lavapipe
+    // cannot pass without actually rendering
+    #[test]
+    fn captures_without_window() {
+        let (allocator, queue) = match headless_queue() {
+            Some(headless) => headless,
+            None if std::env::var_os("REQUIRE_VULKAN").is_some() => panic!("REQUIRE_VULKAN is set but no Vulkan device was found"),
+            None => {
+                eprintln!("Skipping thumbnail test, no Vulkan device");
+                return;
+            }
+        };
+        let mut world = World::new();
+        world.insert_resource(ThumbnailRenderer::new(allocator, queue));
+        world.spawn(Body { spine: vec![Vertebrae { position: [0.0, 0.0, 0.0, 1.0], color: [0.0, 1.0, 0.0, 1.0] }] });
+
+        let thumbnail = capture_thumbnail(&world).expect("thumbnail was not rendered");
+        assert_eq!([thumbnail.width, thumbnail.height], THUMBNAIL_SIZE);
+        assert_eq!(thumbnail.rgba.len(), (THUMBNAIL_SIZE[0] * THUMBNAIL_SIZE[1] * 4) as usize);
+        // The point at the world origin lands in the middle, the corners keep the clear color
+        let pixel = |x: u32, y: u32| {
+            let i = ((y * THUMBNAIL_SIZE[0] + x) * 4) as usize;
+            [thumbnail.rgba[i], thumbnail.rgba[i + 1], thumbnail.rgba[i + 2]]
+        };
+        assert_eq!(pixel(0, 0), [0, 0, 255]);
+        assert_eq!(pixel(THUMBNAIL_SIZE[0] / 2, THUMBNAIL_SIZE[1] / 2), [0, 255, 0]);
+    }
+}
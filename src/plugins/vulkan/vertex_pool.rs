@@ -0,0 +1,335 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use bevy::ecs::entity::Entity;
+use bevy::utils::HashMap;
+use vulkano::buffer::{BufferUsage, CpuAccessibleBuffer};
+use vulkano::memory::allocator::StandardMemoryAllocator;
+
+pub type Vertex = [[f32; 4]; 2];
+
+// Ordered so that sorting the draws groups them by pipeline
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DrawMode {
+    Points,
+    LineStrip,
+    Triangles,
+}
+
+// One buffer per frame in flight, so the CPU never waits on a buffer the GPU is still reading
+const FRAMES_IN_FLIGHT: usize = 3;
+const MIN_CAPACITY: usize = 1024;
+
+// What the ring buffer in `slot` needs before it can be drawn from
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Upload {
+    // Every buffer of the ring is reallocated with this capacity
+    Grow(usize),
+    Write(Range<usize>),
+    Clean,
+}
+
+// Keeps every body's vertices in one CPU side array and tracks which ranges each buffer of the
+// ring is missing, `VertexBuffers` mirrors them to the GPU. Buffers are only reallocated when
+// the vertices outgrow them.
+#[derive(Default)]
+pub struct VertexPool {
+    vertices: Vec<Vertex>,
+    ranges: HashMap<Entity, Range<usize>>,
+    modes: HashMap<Entity, DrawMode>,
+    dirty: [Option<Range<usize>>; FRAMES_IN_FLIGHT],
+    draws: Vec<(DrawMode, Range<usize>)>,
+    draws_changed: bool,
+    capacity: usize,
+    next: usize,
+}
+
+impl VertexPool {
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+
+    pub fn set(&mut self, entity: Entity, mode: DrawMode, vertices: Vec<Vertex>) {
+        if self.modes.insert(entity, mode) != Some(mode) {
+            self.draws_changed = true;
+        }
+        match self.ranges.get(&entity).cloned() {
+            Some(range) if range.len() == vertices.len() => {
+                self.vertices[range.clone()].copy_from_slice(&vertices);
+                self.mark_dirty(range);
+            }
+            Some(_) => {
+                self.remove(entity);
+                self.modes.insert(entity, mode);
+                self.append(entity, vertices);
+            }
+            None => self.append(entity, vertices),
+        }
+    }
+
+    // Everything after the removed range moves down, so those vertices have to be uploaded again
+    pub fn remove(&mut self, entity: Entity) {
+        let removed = match self.ranges.remove(&entity) {
+            Some(removed) => removed,
+            None => return,
+        };
+        self.modes.remove(&entity);
+        self.vertices.drain(removed.clone());
+        for range in self.ranges.values_mut() {
+            if range.start >= removed.end {
+                *range = range.start - removed.len()..range.end - removed.len();
+            }
+        }
+        self.draws_changed = true;
+
+        // Pending ranges may now reach past the end, what is left of them still has to be written
+        let len = self.vertices.len();
+        for dirty in &mut self.dirty {
+            *dirty = dirty.take()
+                .map(|dirty| dirty.start.min(len)..dirty.end.min(len))
+                .filter(|dirty| !dirty.is_empty());
+        }
+        self.mark_dirty(removed.start..len);
+    }
+
+    // List topologies of neighbouring bodies are merged into one draw, every strip needs its own.
+    // Only rebuilt when a body was added, removed, resized or restyled.
+    pub fn draws(&mut self) -> &[(DrawMode, Range<usize>)] {
+        if self.draws_changed {
+            self.draws_changed = false;
+            let mut ranges: Vec<(DrawMode, Range<usize>)> = self.ranges.iter()
+                .filter(|(_, range)| !range.is_empty())
+                .map(|(entity, range)| (self.modes[entity], range.clone()))
+                .collect();
+            ranges.sort_unstable_by_key(|(mode, range)| (*mode, range.start));
+
+            self.draws.clear();
+            for (mode, range) in ranges {
+                match self.draws.last_mut() {
+                    Some((last_mode, last)) if *last_mode == mode && mode != DrawMode::LineStrip && last.end == range.start => {
+                        last.end = range.end;
+                    }
+                    _ => self.draws.push((mode, range)),
+                }
+            }
+        }
+        &self.draws
+    }
+
+    // Advances the ring and returns the slot to draw from with what it is missing
+    pub fn next_upload(&mut self) -> Option<(usize, Upload)> {
+        if self.vertices.is_empty() {
+            return None;
+        }
+        if self.vertices.len() > self.capacity {
+            self.capacity = self.vertices.len().next_power_of_two().max(MIN_CAPACITY);
+            self.dirty = Default::default();
+            self.next = 1;
+            return Some((0, Upload::Grow(self.capacity)));
+        }
+
+        let slot = self.next;
+        self.next = (self.next + 1) % FRAMES_IN_FLIGHT;
+        let upload = match self.dirty[slot].take() {
+            Some(dirty) => Upload::Write(dirty),
+            None => Upload::Clean,
+        };
+        Some((slot, upload))
+    }
+
+    fn append(&mut self, entity: Entity, vertices: Vec<Vertex>) {
+        let start = self.vertices.len();
+        self.vertices.extend(vertices);
+        self.ranges.insert(entity, start..self.vertices.len());
+        self.draws_changed = true;
+        self.mark_dirty(start..self.vertices.len());
+    }
+
+    fn mark_dirty(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        for dirty in &mut self.dirty {
+            *dirty = Some(match dirty.take() {
+                Some(dirty) => dirty.start.min(range.start)..dirty.end.max(range.end),
+                None => range.clone(),
+            });
+        }
+    }
+}
+
+pub struct VertexBuffers {
+    allocator: Arc<StandardMemoryAllocator>,
+    buffers: Vec<Arc<CpuAccessibleBuffer<[Vertex]>>>,
+    capacity: usize,
+}
+
+impl VertexBuffers {
+    pub fn new(allocator: Arc<StandardMemoryAllocator>) -> Self {
+        Self {
+            allocator,
+            buffers: vec![],
+            capacity: 0,
+        }
+    }
+
+    pub fn next_buffer(&mut self, pool: &mut VertexPool) -> Option<Arc<CpuAccessibleBuffer<[Vertex]>>> {
+        let (slot, upload) = pool.next_upload()?;
+        match upload {
+            Upload::Grow(capacity) => {
+                self.capacity = capacity;
+                self.buffers = (0..FRAMES_IN_FLIGHT)
+                    .map(|_| create_buffer(&self.allocator, capacity, pool.vertices()))
+                    .collect();
+            }
+            Upload::Write(range) => {
+                let written = match self.buffers[slot].write() {
+                    Ok(mut buffer) => {
+                        buffer[range.clone()].copy_from_slice(&pool.vertices()[range]);
+                        true
+                    }
+                    Err(_) => false,
+                };
+                // Still in use by a frame that has not finished, a fresh buffer avoids the stall
+                if !written {
+                    self.buffers[slot] = create_buffer(&self.allocator, self.capacity, pool.vertices());
+                }
+            }
+            Upload::Clean => {}
+        }
+        Some(self.buffers[slot].clone())
+    }
+}
+
+fn create_buffer(allocator: &StandardMemoryAllocator, capacity: usize, vertices: &[Vertex]) -> Arc<CpuAccessibleBuffer<[Vertex]>> {
+    let mut data = vertices.to_vec();
+    data.resize(capacity, [[0.0; 4]; 2]);
+    CpuAccessibleBuffer::from_iter(
+        allocator,
+        BufferUsage {
+            vertex_buffer: true,
+            ..Default::default()
+        },
+        false,
+        data,
+    ).expect("Failed to create vertex buffer.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertices(count: usize, value: f32) -> Vec<Vertex> {
+        vec![[[value; 4], [1.0; 4]]; count]
+    }
+
+    // Drives the whole ring once, the way `VertexBuffers` would
+    fn flush_ring(pool: &mut VertexPool) -> Vec<(usize, Upload)> {
+        (0..FRAMES_IN_FLIGHT).filter_map(|_| pool.next_upload()).collect()
+    }
+
+    #[test]
+    fn empty_pool_has_nothing_to_draw() {
+        let mut pool = VertexPool::default();
+        assert_eq!(pool.next_upload(), None);
+        assert!(pool.draws().is_empty());
+    }
+
+    #[test]
+    fn first_upload_grows_the_ring() {
+        let mut pool = VertexPool::default();
+        pool.set(Entity::from_raw(0), DrawMode::Points, vertices(3, 0.0));
+
+        assert_eq!(pool.next_upload(), Some((0, Upload::Grow(MIN_CAPACITY))));
+        // Grown buffers are created from the current vertices, the rest of the ring is up to date
+        assert_eq!(pool.next_upload(), Some((1, Upload::Clean)));
+        assert_eq!(pool.next_upload(), Some((2, Upload::Clean)));
+        assert_eq!(pool.next_upload(), Some((0, Upload::Clean)));
+    }
+
+    #[test]
+    fn append_is_written_to_every_buffer_once() {
+        let mut pool = VertexPool::default();
+        pool.set(Entity::from_raw(0), DrawMode::Points, vertices(3, 0.0));
+        flush_ring(&mut pool);
+
+        pool.set(Entity::from_raw(1), DrawMode::Points, vertices(2, 1.0));
+        assert_eq!(flush_ring(&mut pool), vec![
+            (0, Upload::Write(3..5)),
+            (1, Upload::Write(3..5)),
+            (2, Upload::Write(3..5)),
+        ]);
+        assert_eq!(flush_ring(&mut pool), vec![(0, Upload::Clean), (1, Upload::Clean), (2, Upload::Clean)]);
+        assert_eq!(&pool.vertices()[3..5], &vertices(2, 1.0)[..]);
+    }
+
+    #[test]
+    fn remove_shifts_the_following_bodies() {
+        let mut pool = VertexPool::default();
+        for (i, count) in [2, 3, 4].into_iter().enumerate() {
+            pool.set(Entity::from_raw(i as u32), DrawMode::Points, vertices(count, i as f32));
+        }
+        flush_ring(&mut pool);
+
+        pool.remove(Entity::from_raw(1));
+        assert_eq!(pool.vertices().len(), 6);
+        assert_eq!(&pool.vertices()[2..], &vertices(4, 2.0)[..]);
+        assert!(flush_ring(&mut pool).into_iter().all(|(_, upload)| upload == Upload::Write(2..6)));
+        assert_eq!(pool.draws(), &[(DrawMode::Points, 0..6)]);
+    }
+
+    // Removing the tail while some buffers still wait for an earlier append must not leave
+    // them with a range past the end of the vertices
+    #[test]
+    fn remove_trims_pending_ranges() {
+        let mut pool = VertexPool::default();
+        for i in 0..3 {
+            pool.set(Entity::from_raw(i), DrawMode::Points, vertices(2, i as f32));
+        }
+        flush_ring(&mut pool);
+
+        pool.set(Entity::from_raw(3), DrawMode::Points, vertices(2, 3.0));
+        assert_eq!(pool.next_upload(), Some((0, Upload::Write(6..8))));
+        pool.remove(Entity::from_raw(3));
+        pool.remove(Entity::from_raw(2));
+
+        let len = pool.vertices().len();
+        for (_, upload) in flush_ring(&mut pool) {
+            match upload {
+                Upload::Write(range) => assert!(range.start <= range.end && range.end <= len, "{:?} past {}", range, len),
+                Upload::Clean => {}
+                Upload::Grow(_) => panic!("shrinking must not grow the ring"),
+            }
+        }
+    }
+
+    #[test]
+    fn growing_past_capacity_reallocates() {
+        let mut pool = VertexPool::default();
+        pool.set(Entity::from_raw(0), DrawMode::Points, vertices(MIN_CAPACITY, 0.0));
+        flush_ring(&mut pool);
+
+        pool.set(Entity::from_raw(1), DrawMode::Points, vertices(1, 1.0));
+        assert_eq!(pool.next_upload(), Some((0, Upload::Grow(MIN_CAPACITY * 2))));
+    }
+
+    #[test]
+    fn draws_merge_lists_but_not_strips() {
+        let mut pool = VertexPool::default();
+        pool.set(Entity::from_raw(0), DrawMode::Points, vertices(2, 0.0));
+        pool.set(Entity::from_raw(1), DrawMode::LineStrip, vertices(3, 1.0));
+        pool.set(Entity::from_raw(2), DrawMode::LineStrip, vertices(3, 2.0));
+        pool.set(Entity::from_raw(3), DrawMode::Points, vertices(1, 3.0));
+        pool.set(Entity::from_raw(4), DrawMode::Points, vertices(1, 4.0));
+
+        assert_eq!(pool.draws(), &[
+            (DrawMode::Points, 0..2),
+            (DrawMode::Points, 8..10),
+            (DrawMode::LineStrip, 2..5),
+            (DrawMode::LineStrip, 5..8),
+        ]);
+
+        pool.set(Entity::from_raw(0), DrawMode::LineStrip, vertices(2, 0.0));
+        assert_eq!(pool.draws()[0], (DrawMode::Points, 8..10));
+    }
+}
@@ -3,11 +3,16 @@
 layout (location=0) in vec4 point;
 layout (location=1) in vec4 color_input;
 
+layout (push_constant) uniform PushConstants {
+    mat4 view_projection;
+} push_constants;
+
 layout (location=0) out vec4 color;
 
 void main() {
     gl_PointSize=5.0;
 
-    gl_Position = point;
+    // Bodies are 2D, older saves still have w stored as 0
+    gl_Position = push_constants.view_projection * vec4(point.xyz, 1.0);
     color=color_input;
 }
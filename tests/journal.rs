@@ -0,0 +1,57 @@
+use std::{env, fs, process};
+
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::input::InputPlugin;
+use bevy::prelude::*;
+use bevy::window::{CursorMoved, WindowId};
+
+use game::plugins::save_load::journal::{replay_journal, start_recording, start_replay, stop_recording, update_journal, InputJournal, JournalMode};
+use game::plugins::save_load::saved_components;
+use game::plugins::Components;
+
+fn world() -> World {
+    let mut app = App::new();
+    app.add_plugin(CorePlugin::default())
+        .add_plugin(InputPlugin::default())
+        .add_plugin(Components::default())
+        .add_event::<CursorMoved>()
+        .init_resource::<Time>()
+        .init_resource::<JournalMode>()
+        .insert_resource(saved_components());
+    std::mem::take(&mut app.world)
+}
+
+fn wheel() -> MouseWheel {
+    MouseWheel { unit: MouseScrollUnit::Line, x: 0.0, y: 2.0 }
+}
+
+// Zoom and pan are driven by the wheel and the cursor, a replay has to send both again
+#[test]
+fn replays_wheel_and_cursor() {
+    let dir = env::temp_dir().join(format!("game-journal-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let mut world = world();
+
+    start_recording(&mut world, &dir);
+    update_journal(&mut world);
+    world.send_event(wheel());
+    world.send_event(CursorMoved { id: WindowId::primary(), position: Vec2::new(10.0, 20.0) });
+    world.send_event(CursorMoved { id: WindowId::primary(), position: Vec2::new(30.0, 40.0) });
+    update_journal(&mut world);
+    stop_recording(&mut world).unwrap();
+
+    let journal = InputJournal::read(&dir).unwrap();
+    assert_eq!(journal.frames.len(), 1);
+    assert_eq!(journal.frames[0].mouse_wheel, [wheel()]);
+    assert_eq!(journal.frames[0].cursor_position, Some([30.0, 40.0]));
+
+    start_replay(&mut world, &dir);
+    update_journal(&mut world);
+    replay_journal(&mut world);
+    let wheel_events: Vec<_> = world.resource::<Events<MouseWheel>>().get_reader().iter(world.resource::<Events<MouseWheel>>()).cloned().collect();
+    assert_eq!(wheel_events, [wheel()]);
+    let cursor_events: Vec<_> = world.resource::<Events<CursorMoved>>().get_reader().iter(world.resource::<Events<CursorMoved>>()).map(|event| event.position).collect();
+    assert_eq!(cursor_events, [Vec2::new(30.0, 40.0)]);
+
+    fs::remove_dir_all(dir).unwrap();
+}
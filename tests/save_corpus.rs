@@ -0,0 +1,63 @@
+use std::any::type_name;
+use std::fs;
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy::reflect::FromReflect;
+
+use game::plugins::components::{BodyRenderStyle, PersistentId};
+use game::plugins::Components;
+use game::plugins::save_load::migration::deserialize_versioned;
+
+// Every save format version that was ever written, with the entities and components it has to load into
+const CORPUS: [(&str, usize, usize); 3] = [
+    ("v0_from_world.scn.ron", 3, 7),
+    ("v1_quick_save.scn.ron", 3, 7),
+    ("v1_styled_bodies.scn.ron", 3, 11),
+];
+
+fn type_registry() -> AppTypeRegistry {
+    let mut app = App::new();
+    app.add_plugin(CorePlugin::default())
+        .add_plugin(Components::default());
+    app.world.resource::<AppTypeRegistry>().clone()
+}
+
+#[test]
+fn old_saves_keep_loading() {
+    let type_registry = type_registry();
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/save_corpus");
+
+    for (file, entities, components) in CORPUS {
+        let bytes = fs::read(corpus.join(file)).unwrap();
+        let scene = deserialize_versioned(&bytes, &type_registry).unwrap_or_else(|error| panic!("{}: {}", file, error));
+        assert_eq!(scene.entities.len(), entities, "{}", file);
+        assert_eq!(scene.entities.iter().map(|entity| entity.components.len()).sum::<usize>(), components, "{}", file);
+    }
+}
+
+// Enum variants and integers past i64::MAX are what a `ron::Value` loses
+#[test]
+fn current_saves_keep_enums_and_large_ids() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/save_corpus");
+    let bytes = fs::read(corpus.join("v1_styled_bodies.scn.ron")).unwrap();
+    let scene = deserialize_versioned(&bytes, &type_registry()).unwrap();
+    let components = |type_name: &str| scene.entities.iter()
+        .flat_map(|entity| &entity.components)
+        .filter(|component| component.type_name() == type_name)
+        .collect::<Vec<_>>();
+
+    let styles: Vec<_> = components(type_name::<BodyRenderStyle>()).into_iter().filter_map(|style| BodyRenderStyle::from_reflect(&**style)).collect();
+    assert_eq!(styles, [BodyRenderStyle::Polyline { width: 0.02 }, BodyRenderStyle::LineStrip, BodyRenderStyle::Points]);
+    let ids: Vec<_> = components(type_name::<PersistentId>()).into_iter().filter_map(|id| PersistentId::from_reflect(&**id)).map(|id| id.0).collect();
+    assert_eq!(ids, [u64::MAX]);
+}
+
+#[test]
+fn corpus_covers_every_file() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/save_corpus");
+    for entry in fs::read_dir(corpus).unwrap() {
+        let name = entry.unwrap().file_name().into_string().unwrap();
+        assert!(CORPUS.iter().any(|(file, _, _)| *file == name), "{} is not in the corpus table", name);
+    }
+}
@@ -0,0 +1,94 @@
+use std::any::type_name;
+
+use bevy::prelude::*;
+use bevy::reflect::FromReflect;
+
+use game::plugins::components::{Body, BodyRenderStyle, Name, PersistentId, Save, Vertebrae};
+use game::plugins::save_load::format::{decode, encode, SaveFormat};
+use game::plugins::save_load::hash::{scene_hash, world_hash};
+use game::plugins::save_load::saved_components;
+use game::plugins::save_load::scene::build_save_scene;
+use game::plugins::Components;
+
+fn world() -> World {
+    let mut app = App::new();
+    app.add_plugin(CorePlugin::default())
+        .add_plugin(Components::default())
+        .insert_resource(saved_components());
+    std::mem::take(&mut app.world)
+}
+
+fn round_trip(world: &World, format: SaveFormat) -> DynamicScene {
+    let type_registry = world.resource::<AppTypeRegistry>();
+    let bytes = encode(&build_save_scene(world), type_registry, format).unwrap();
+    decode(&bytes, type_registry, format).unwrap()
+}
+
+fn body() -> Body {
+    Body {
+        spine: vec![
+            Vertebrae { position: [0.0, 0.0, 0.0, 1.0], color: [0.0, 1.0, 0.0, 1.0] },
+            Vertebrae { position: [0.5, -0.25, 0.0, 1.0], color: [1.0, 1.0, 1.0, 1.0] },
+        ],
+    }
+}
+
+#[test]
+fn persistent_ids_survive_ron() {
+    let mut world = world();
+    // Ids past i64::MAX are the ones a lossy loader turns into floats
+    let mut ids: Vec<u64> = (0..1000)
+        .map(|_| PersistentId::new())
+        .chain([PersistentId(0), PersistentId(i64::MAX as u64 + 1), PersistentId(u64::MAX)])
+        .map(|id| {
+            world.spawn((body(), id, Save));
+            id.0
+        })
+        .collect();
+
+    let scene = round_trip(&world, SaveFormat::Ron);
+    let mut loaded: Vec<u64> = scene.entities.iter()
+        .flat_map(|entity| &entity.components)
+        .filter(|component| component.type_name() == type_name::<PersistentId>())
+        .filter_map(|id| PersistentId::from_reflect(&**id))
+        .map(|id| id.0)
+        .collect();
+
+    ids.sort_unstable();
+    loaded.sort_unstable();
+    assert_eq!(ids, loaded);
+}
+
+#[test]
+fn default_ids_are_unique() {
+    assert_ne!(PersistentId::default(), PersistentId::default());
+}
+
+#[test]
+fn hash_is_the_same_after_every_round_trip() {
+    let mut world = world();
+    world.spawn((body(), Name("Spine".to_string()), PersistentId::new(), Save));
+    world.spawn((Name("Spineless".to_string()), Save));
+
+    let live = world_hash(&world);
+    for format in SaveFormat::ALL {
+        assert_eq!(scene_hash(&round_trip(&world, format)), live, "{:?}", format);
+    }
+}
+
+#[test]
+fn render_styles_survive_every_format() {
+    let mut world = world();
+    world.spawn((body(), BodyRenderStyle::Polyline { width: 0.02 }, Save));
+    world.spawn((body(), BodyRenderStyle::LineStrip, Save));
+
+    for format in SaveFormat::ALL {
+        let scene = round_trip(&world, format);
+        let styles: Vec<_> = scene.entities.iter()
+            .flat_map(|entity| &entity.components)
+            .filter(|component| component.type_name() == type_name::<BodyRenderStyle>())
+            .filter_map(|style| BodyRenderStyle::from_reflect(&**style))
+            .collect();
+        assert_eq!(styles, [BodyRenderStyle::Polyline { width: 0.02 }, BodyRenderStyle::LineStrip], "{:?}", format);
+    }
+}
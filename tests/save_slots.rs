@@ -0,0 +1,100 @@
+use std::path::PathBuf;
+use std::{env, fs, process};
+
+use bevy::prelude::*;
+
+use game::plugins::components::{Body, Save, Vertebrae};
+use game::plugins::save_load::autosave::AUTOSAVE_SLOT;
+use game::plugins::save_load::error::SaveError;
+use game::plugins::save_load::saved_components;
+use game::plugins::save_load::slots::{SaveSlots, QUICK_SLOT};
+use game::plugins::Components;
+
+fn world() -> World {
+    let mut app = App::new();
+    app.add_plugin(CorePlugin::default())
+        .add_plugin(Components::default())
+        .init_resource::<Time>()
+        .insert_resource(saved_components());
+    std::mem::take(&mut app.world)
+}
+
+fn body() -> Body {
+    Body { spine: vec![Vertebrae { position: [0.0, 0.0, 0.0, 1.0], color: [1.0, 1.0, 1.0, 1.0] }] }
+}
+
+fn slot_dir(test: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("game-save-slots-{}-{}", test, process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+// Two quick saves, one and then two bodies, with the newer one damaged and the manifest gone
+fn crash_after_second_save(dir: &PathBuf, damage: fn(Vec<u8>) -> Vec<u8>) -> (World, SaveSlots) {
+    let slots = SaveSlots::new(dir);
+    let mut world = world();
+    world.spawn((body(), Save));
+    slots.save(&world, QUICK_SLOT).unwrap();
+    world.spawn((body(), Save));
+    slots.save(&world, QUICK_SLOT).unwrap();
+
+    let slot_dir = slots.slot_dir(QUICK_SLOT);
+    let newest = slot_dir.join("2.scn.ron");
+    fs::write(&newest, damage(fs::read(&newest).unwrap())).unwrap();
+    fs::remove_file(slot_dir.join("manifest.ron")).unwrap();
+    (world, slots)
+}
+
+// The damaged save still decodes, only its checksum tells it apart
+#[test]
+fn rebuilt_manifest_keeps_checksums() {
+    let dir = slot_dir("checksums");
+    let (world, slots) = crash_after_second_save(&dir, |bytes| String::from_utf8(bytes).unwrap().replacen("1.0", "0.5", 1).into_bytes());
+
+    let scene = slots.read_scene(QUICK_SLOT, world.resource::<AppTypeRegistry>()).unwrap();
+    assert_eq!(scene.entities.len(), 1);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// Saves written before checksums were kept next to them are only caught by failing to decode
+#[test]
+fn undecodable_save_falls_back_without_checksum() {
+    let dir = slot_dir("undecodable");
+    let (world, slots) = crash_after_second_save(&dir, |bytes| bytes[..bytes.len() / 2].to_vec());
+    fs::remove_file(slots.slot_dir(QUICK_SLOT).join("2.scn.ron.crc32")).unwrap();
+
+    let scene = slots.read_scene(QUICK_SLOT, world.resource::<AppTypeRegistry>()).unwrap();
+    assert_eq!(scene.entities.len(), 1);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// The input journal is kept in the slots directory
+#[test]
+fn journal_name_is_reserved() {
+    let dir = slot_dir("journal");
+    let slots = SaveSlots::new(&dir);
+    let world = world();
+    for name in ["journal", "Journal"] {
+        assert!(matches!(slots.save(&world, name), Err(SaveError::ReservedSlot(_))), "{}", name);
+    }
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn reserved_slots_are_not_managed_by_name() {
+    let dir = slot_dir("reserved");
+    let slots = SaveSlots::new(&dir);
+    let mut world = world();
+    world.spawn((body(), Save));
+    slots.save(&world, QUICK_SLOT).unwrap();
+    slots.create(&world, "mine").unwrap();
+
+    for reserved in [QUICK_SLOT, AUTOSAVE_SLOT] {
+        assert!(matches!(slots.create(&world, reserved), Err(SaveError::ReservedSlot(_))), "{}", reserved);
+        assert!(matches!(slots.delete(reserved), Err(SaveError::ReservedSlot(_))), "{}", reserved);
+        assert!(matches!(slots.rename("mine", reserved), Err(SaveError::ReservedSlot(_))), "{}", reserved);
+    }
+    assert!(slots.metadata(QUICK_SLOT).is_ok());
+    slots.delete("mine").unwrap();
+    fs::remove_dir_all(dir).unwrap();
+}
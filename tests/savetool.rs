@@ -0,0 +1,33 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const CORPUS: [&str; 3] = ["v0_from_world.scn.ron", "v1_quick_save.scn.ron", "v1_styled_bodies.scn.ron"];
+
+fn corpus(file: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/save_corpus").join(file)
+}
+
+fn savetool(args: &[&Path]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_savetool")).args(args).output().unwrap();
+    assert!(output.status.success(), "savetool {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+    output
+}
+
+#[test]
+fn reads_every_corpus_save() {
+    for file in CORPUS {
+        let path = corpus(file);
+        for command in ["show", "validate", "hash"] {
+            savetool(&[Path::new(command), &path]);
+        }
+    }
+}
+
+// The version 0 save migrates into exactly what the version 1 quick save holds
+#[test]
+fn migrated_save_matches_current_one() {
+    let [old, new] = [CORPUS[0], CORPUS[1]].map(corpus);
+    savetool(&[Path::new("diff"), &old, &new]);
+    let hashes = [&old, &new].map(|path| savetool(&[Path::new("hash"), path]).stdout);
+    assert_eq!(hashes[0], hashes[1]);
+}